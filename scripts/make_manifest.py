@@ -69,9 +69,17 @@ def main():
         "determinism": {
             "no_rng": True,
             "softmax": "stable max-subtraction; uniform fallback if sumexp==0 or NaN",
-            "state_hash": "sha256 over (config, window_KV, memory_KV, ages) in little-endian f64/u64 bytes",
+            "state_hash": "sha256 over (config, window_KV, memory_KV, ages, occupancy) in little-endian f64/u64 bytes",
             "tie_break": "argmax ties -> lowest index; LRU ties -> lowest index",
         },
+        "slot_decay": {
+            # defaults: no decay, no expiry (v0)
+            "key_decay": 1.0,
+            "value_decay": 1.0,
+            "slot_ttl": None,
+            "rule": "each step: age += 1; km *= key_decay; vm *= value_decay; "
+                    "slots with age > slot_ttl are zeroed (free); novel writes take the lowest free slot first",
+        },
         "pinned_params": {
             # core demo
             "L": 8,
@@ -99,6 +107,7 @@ def main():
             "fid_tau_reuse": 0.9,
             "fid_tau_novel": 0.5,
             "fid_g_write": 0.25,
+            "fid_k_evict": -10.0,
             "fid_n_max": 6,
         },
         "expected": {
@@ -113,8 +122,8 @@ def main():
 
             "fidelity_decay": {
                 "baseline": {"n0": "MISS", "n1": "MISS", "n2": "MISS", "n3": "MISS", "n4": "MISS", "n5": "MISS", "n6": "MISS"},
-                "g025":     {"n0": "HIT",  "n1": "HIT",  "n2": "HIT",  "n3": "HIT",  "n4": "MISS", "n5": "MISS", "n6": "MISS"},
-                "params":   {"L": 1, "M": 1, "d": 8, "A": 60.0, "thr": 5.0, "tau_reuse": 0.9, "tau_novel": 0.5, "g_write": 0.25, "n_max": 6},
+                "g025":     {"n0": "HIT",  "n1": "HIT",  "n2": "MISS", "n3": "MISS", "n4": "MISS", "n5": "MISS", "n6": "MISS"},
+                "params":   {"L": 1, "M": 1, "d": 8, "A": 60.0, "thr": 5.0, "tau_reuse": 0.9, "tau_novel": 0.5, "g_write": 0.25, "k_evict": -10.0, "n_max": 6},
             },
         },
         "files": files,
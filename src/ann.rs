@@ -0,0 +1,141 @@
+//! Random-hyperplane LSH over memory-slot keys, used by the write path's
+//! nearest-slot search in place of the exact linear cosine scan.
+
+use std::collections::HashMap;
+
+use crate::{KVMemError, KVMemV0, Scalar};
+
+/// Parameters of the slot index enabled with `KVMemV0::enable_lsh`.
+///
+/// Each of `tables` hash tables buckets slots by the signs of `bits` random
+/// projections of their key. Hyperplanes are drawn from a splitmix64 stream
+/// seeded with `seed`, so a given config always builds the same index and the
+/// same write decisions on every platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LshConfig {
+    pub tables: usize,
+    pub bits: usize,
+    pub seed: u64,
+}
+
+impl Default for LshConfig {
+    fn default() -> Self {
+        Self { tables: 32, bits: 8, seed: 0 }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct LshIndex {
+    cfg: LshConfig,
+    d: usize,
+    // tables * bits rows of d.
+    planes: Vec<f64>,
+    buckets: Vec<HashMap<u64, Vec<usize>>>,
+    // slots * tables codes, to find a slot's old buckets on update.
+    codes: Vec<u64>,
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl LshIndex {
+    fn new(cfg: LshConfig, d: usize) -> Result<Self, KVMemError> {
+        if cfg.tables == 0 {
+            return Err(KVMemError::IndexConfig("lsh tables must be > 0"));
+        }
+        if cfg.bits == 0 || cfg.bits > 64 {
+            return Err(KVMemError::IndexConfig("lsh bits must be in 1..=64"));
+        }
+        // Uniform components in [-1, 1) rather than Gaussian: no transcendental
+        // functions, so planes are bit-identical across platforms.
+        let mut state = cfg.seed;
+        let planes = (0..cfg.tables * cfg.bits * d)
+            .map(|_| (splitmix64(&mut state) >> 11) as f64 / (1u64 << 52) as f64 - 1.0)
+            .collect();
+        Ok(Self { cfg, d, planes, buckets: vec![HashMap::new(); cfg.tables], codes: Vec::new() })
+    }
+
+    fn code<S: Scalar>(&self, t: usize, key: &[S]) -> u64 {
+        let mut code = 0u64;
+        for b in 0..self.cfg.bits {
+            let p = &self.planes[(t * self.cfg.bits + b) * self.d..][..self.d];
+            let dot: f64 = p.iter().zip(key).map(|(x, y)| x * y.to_f64()).sum();
+            if dot >= 0.0 {
+                code |= 1 << b;
+            }
+        }
+        code
+    }
+
+    fn insert<S: Scalar>(&mut self, slot: usize, key: &[S]) {
+        for t in 0..self.cfg.tables {
+            let c = self.code(t, key);
+            self.codes[slot * self.cfg.tables + t] = c;
+            self.buckets[t].entry(c).or_default().push(slot);
+        }
+    }
+
+    pub(crate) fn update<S: Scalar>(&mut self, slot: usize, key: &[S]) {
+        for t in 0..self.cfg.tables {
+            let old = self.codes[slot * self.cfg.tables + t];
+            if let Some(b) = self.buckets[t].get_mut(&old) {
+                b.retain(|&s| s != slot);
+                if b.is_empty() {
+                    self.buckets[t].remove(&old);
+                }
+            }
+        }
+        self.insert(slot, key);
+    }
+
+    /// Slots sharing a bucket with `key` in any table, ascending, into `out`
+    /// (cleared first; its capacity is reused).
+    pub(crate) fn candidates_into<S: Scalar>(&self, key: &[S], out: &mut Vec<usize>) {
+        out.clear();
+        for t in 0..self.cfg.tables {
+            if let Some(b) = self.buckets[t].get(&self.code(t, key)) {
+                out.extend_from_slice(b);
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+    }
+}
+
+impl<S: Scalar> KVMemV0<S> {
+    /// Uses an LSH index for the write path's nearest-slot search. The index is
+    /// built from the current slots and kept in sync on every slot write; when a
+    /// key shares no bucket with any slot the search falls back to the exact scan.
+    ///
+    /// The search is approximate, so write decisions (and hence state) may differ
+    /// from the exact scan. The index is not part of the state: it is not hashed
+    /// or snapshotted and must be re-enabled after a restore. Keeping it in sync
+    /// moves slots between bucket `Vec`s, so with an index `step_into` allocates.
+    pub fn enable_lsh(&mut self, cfg: LshConfig) -> Result<(), KVMemError> {
+        let mut ix = LshIndex::new(cfg, self.d)?;
+        ix.codes = vec![0; self.m_slots * cfg.tables];
+        for j in 0..self.m_slots {
+            ix.insert(j, self.km_row(j));
+        }
+        self.index = Some(ix);
+        Ok(())
+    }
+
+    pub fn disable_lsh(&mut self) {
+        self.index = None;
+    }
+
+    pub fn lsh_config(&self) -> Option<LshConfig> {
+        self.index.as_ref().map(|ix| ix.cfg)
+    }
+
+    /// `nearest_slot` by the exact linear scan, ignoring any index.
+    pub fn nearest_slot_exact(&self, k: &[S]) -> Option<(usize, f64)> {
+        self.slot_occupancy().contains(&true).then(|| self.max_sim_over(k, self.occupied_slots()))
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Instant;
+
+use kv_memory_vsc_v0::{KVMemConfig, KVMemV0, LshConfig};
+
+// Deterministic xorshift stream.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn vec(&mut self, d: usize, amp: f64) -> Vec<f64> {
+        (0..d).map(|_| (2.0 * self.unit() - 1.0) * amp).collect()
+    }
+}
+
+// Write-path branch the nearest-slot result leads to, with the slot it names.
+fn decision(cfg: &KVMemConfig, nearest: Option<(usize, f64)>) -> (&'static str, Option<usize>) {
+    match nearest {
+        Some((j, s)) if s >= cfg.tau_reuse => ("reuse", Some(j)),
+        Some((j, s)) if s >= cfg.tau_novel => ("gate", Some(j)),
+        _ => ("novel", None),
+    }
+}
+
+// Memory with every slot pre-filled with a random key, plus the write stream:
+// fresh keys, near-duplicates of stored keys (reuse) and noisier copies (gate).
+fn setup(cfg: &KVMemConfig, n_writes: usize) -> (KVMemV0, Vec<Vec<f64>>) {
+    let (m, d) = (cfg.m_slots, cfg.d);
+    let mut rng = Rng(0x2545f4914f6cdd1d);
+    let mut state = KVMemV0::from_config(cfg).unwrap().to_state();
+    state.km = (0..m).map(|_| rng.vec(d, 1.0)).collect();
+    state.vm = (0..m).map(|_| rng.vec(d, 1.0)).collect();
+    state.occupied = vec![true; m];
+
+    let keys = (0..n_writes)
+        .map(|_| {
+            let base = &state.km[(rng.next() % m as u64) as usize];
+            let noise = match rng.next() % 10 {
+                0..=3 => return rng.vec(d, 1.0),
+                4..=6 => 0.05,
+                _ => 0.4,
+            };
+            base.iter().map(|x| x + (2.0 * rng.unit() - 1.0) * noise).collect()
+        })
+        .collect();
+    (KVMemV0::from_state(state).unwrap(), keys)
+}
+
+fn throughput(mut m: KVMemV0, keys: &[Vec<f64>]) -> f64 {
+    let t = Instant::now();
+    for k in keys {
+        m.ingest(k, k, true);
+    }
+    keys.len() as f64 / t.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1).map(|a| a.parse::<usize>().expect("usage: bench_ann [m_slots] [n_writes]"));
+    let m_slots = args.next().unwrap_or(4096);
+    let n_writes = args.next().unwrap_or(2000);
+    let d = 32usize;
+    let lsh = LshConfig::default();
+
+    let cfg = KVMemConfig::builder()
+        .l_window(8)
+        .m_slots(m_slots)
+        .d(d)
+        .tau_reuse(0.9)
+        .tau_novel(0.5)
+        .g_write(0.5)
+        .build()
+        .expect("valid config");
+    let (exact, keys) = setup(&cfg, n_writes);
+    let mut indexed = exact.clone();
+    indexed.enable_lsh(lsh).unwrap();
+
+    println!("ann_params: M={} d={} n_writes={} tables={} bits={} seed={}",
+        m_slots, d, n_writes, lsh.tables, lsh.bits, lsh.seed);
+
+    let exact_wps = throughput(exact, &keys);
+    let lsh_wps = throughput(indexed.clone(), &keys);
+    println!("exact: writes_per_sec={:.0}", exact_wps);
+    println!("lsh:   writes_per_sec={:.0} speedup={:.2}x", lsh_wps, lsh_wps / exact_wps);
+
+    // Replay on the indexed memory, comparing its decision with the exact scan's
+    // on the same state before each write.
+    let mut agree = 0usize;
+    let mut per_branch = [("reuse", 0usize, 0usize), ("gate", 0, 0), ("novel", 0, 0)];
+    for k in &keys {
+        let want = decision(&cfg, indexed.nearest_slot_exact(k));
+        let got = decision(&cfg, indexed.nearest_slot(k));
+        let row = per_branch.iter_mut().find(|(name, _, _)| *name == want.0).unwrap();
+        row.2 += 1;
+        if got == want {
+            agree += 1;
+            row.1 += 1;
+        }
+        indexed.ingest(k, k, true);
+    }
+    println!("decision_agreement: {}/{} ({:.1}%)", agree, keys.len(), 100.0 * agree as f64 / keys.len() as f64);
+    for (name, a, n) in per_branch {
+        println!("  {}: {}/{}", name, a, n);
+    }
+}
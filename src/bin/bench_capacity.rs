@@ -1,4 +1,4 @@
-use kv_memory_vsc_v0::KVMemV0;
+use kv_memory_vsc_v0::{KVMemConfig, KVMemV0, KeyOrigin, ReadMode, Replacement};
 
 fn e(d: usize, i: usize, s: f64) -> Vec<f64> {
     let mut v = vec![0.0; d];
@@ -45,10 +45,42 @@ fn fill(m: &mut KVMemV0, d: usize, n: usize) {
     }
 }
 
-fn ask(m: &mut KVMemV0, d: usize, idx: usize) -> Vec<f64> {
-    let q = e(d, idx, 10.0);
-    let z = vec![0.0; d];
-    m.step(q, z.clone(), z, false)
+// Read-only probes of facts 0..3 against one state: asking does not push into
+// the window or age the slots.
+fn ask_all(m: &KVMemV0, d: usize) -> [Vec<f64>; 3] {
+    let qs: Vec<Vec<f64>> = (0..3).map(|idx| e(d, idx, 10.0)).collect();
+    m.query_batch(&qs).try_into().expect("three answers")
+}
+
+// Key that received the most attention weight when probing fact idx.
+fn source(m: &KVMemV0, d: usize, idx: usize) -> String {
+    match m.query_explain(&e(d, idx, 10.0)).argmax() {
+        Some(KeyOrigin::Memory(j)) => format!("slot{}", j),
+        Some(KeyOrigin::Window(p)) => format!("window{}", p),
+        None => "none".to_string(),
+    }
+}
+
+// Three facts into two slots, with one filler between writes.
+fn slot_pressure(m: &mut KVMemV0, d: usize, n_fill: usize) -> [Vec<f64>; 3] {
+    write_fact(m, d, 0);
+    fill(m, d, 1);
+    write_fact(m, d, 1);
+    fill(m, d, 1);
+    write_fact(m, d, 2);
+    fill(m, d, n_fill);
+    ask_all(m, d)
+}
+
+// One fact in memory, then a long window of weakly matching distractors whose
+// values point elsewhere. In a joint softmax their count alone outweighs the slot.
+fn window_pressure(cfg: &KVMemConfig, d: usize, l_window: usize) -> Vec<f64> {
+    let mut m = KVMemV0::from_config(&KVMemConfig { l_window, m_slots: 1, ..cfg.clone() }).unwrap();
+    m.ingest(&e(d, 0, 3.0), &e(d, 0, 100.0), true);
+    for _ in 0..l_window {
+        m.ingest(&e(d, 0, 2.0), &e(d, 1, 100.0), false);
+    }
+    m.query(&e(d, 0, 3.0))
 }
 
 fn main() {
@@ -57,38 +89,32 @@ fn main() {
     let n_fill = 64usize;
     let thr = 5.0f64;
 
-    let tau_reuse = 0.85f64;
-    let tau_novel = 0.50f64;
-    let g_write = 1.0f64;
+    let cfg = KVMemConfig::builder()
+        .l_window(l)
+        .d(d)
+        .tau_reuse(0.85)
+        .tau_novel(0.50)
+        .g_write(1.0)
+        .build()
+        .expect("valid config");
+    let with_slots = |m_slots: usize| KVMemV0::from_config(&KVMemConfig { m_slots, ..cfg.clone() }).unwrap();
 
-    let mut base = KVMemV0::new(l, 0, d, tau_reuse, tau_novel, g_write);
+    let mut base = with_slots(0);
     write_fact(&mut base, d, 0);
     write_fact(&mut base, d, 1);
     write_fact(&mut base, d, 2);
     fill(&mut base, d, n_fill);
-    let b0 = ask(&mut base, d, 0);
-    let b1 = ask(&mut base, d, 1);
-    let b2 = ask(&mut base, d, 2);
-
-    let mut m2 = KVMemV0::new(l, 2, d, tau_reuse, tau_novel, g_write);
-    write_fact(&mut m2, d, 0);
-    fill(&mut m2, d, 1);
-    write_fact(&mut m2, d, 1);
-    fill(&mut m2, d, 1);
-    write_fact(&mut m2, d, 2);
-    fill(&mut m2, d, n_fill);
-    let m20 = ask(&mut m2, d, 0);
-    let m21 = ask(&mut m2, d, 1);
-    let m22 = ask(&mut m2, d, 2);
-
-    let mut m3 = KVMemV0::new(l, 3, d, tau_reuse, tau_novel, g_write);
+    let [b0, b1, b2] = ask_all(&base, d);
+
+    let mut m2 = with_slots(2);
+    let [m20, m21, m22] = slot_pressure(&mut m2, d, n_fill);
+
+    let mut m3 = with_slots(3);
     write_fact(&mut m3, d, 0);
     write_fact(&mut m3, d, 1);
     write_fact(&mut m3, d, 2);
     fill(&mut m3, d, n_fill);
-    let m30 = ask(&mut m3, d, 0);
-    let m31 = ask(&mut m3, d, 1);
-    let m32 = ask(&mut m3, d, 2);
+    let [m30, m31, m32] = ask_all(&m3, d);
 
     println!("capacity_params: L={} d={} n_fill={} M2=2 M3=3 thr={}", l, d, n_fill, thr);
     println!("baseline: A={} B={} C={}",
@@ -107,9 +133,46 @@ fn main() {
         status(&m32, 2, thr),
     );
 
+    println!("m3_sources: A={} B={} C={}", source(&m3, d, 0), source(&m3, d, 1), source(&m3, d, 2));
+
     println!("baseline_state_sha256: {}", base.state_sha256());
     println!("m2_state_sha256: {}", m2.state_sha256());
     println!("m2_memory_kv_sha256: {}", m2.memory_kv_sha256());
     println!("m3_state_sha256: {}", m3.state_sha256());
     println!("m3_memory_kv_sha256: {}", m3.memory_kv_sha256());
+
+    for r in Replacement::ALL {
+        let mut m = KVMemV0::from_config(&KVMemConfig { m_slots: 2, replacement: r, ..cfg.clone() }).unwrap();
+        let [a, b, c] = slot_pressure(&mut m, d, n_fill);
+        println!("m2_{}: A={} B={} C={}",
+            r.name(),
+            status(&a, 0, thr),
+            status(&b, 1, thr),
+            status(&c, 2, thr),
+        );
+    }
+
+    let l_long = 256usize;
+    println!("read_mode_params: window_pressure L={} M=1", l_long);
+    let modes = [
+        ("joint", ReadMode::Joint),
+        ("fixed_gate_0.75", ReadMode::FixedGate { memory_weight: 0.75 }),
+        ("score_gate_t1", ReadMode::ScoreGate { temperature: 1.0 }),
+    ];
+    for (name, read_mode) in modes {
+        let mode_cfg = KVMemConfig { read_mode, ..cfg.clone() };
+        let mut m = KVMemV0::from_config(&KVMemConfig { m_slots: 3, ..mode_cfg.clone() }).unwrap();
+        write_fact(&mut m, d, 0);
+        write_fact(&mut m, d, 1);
+        write_fact(&mut m, d, 2);
+        fill(&mut m, d, n_fill);
+        let [a, b, c] = ask_all(&m, d);
+        println!("{}: m3 A={} B={} C={} window_pressure={}",
+            name,
+            status(&a, 0, thr),
+            status(&b, 1, thr),
+            status(&c, 2, thr),
+            status(&window_pressure(&mode_cfg, d, l_long), 0, thr),
+        );
+    }
 }
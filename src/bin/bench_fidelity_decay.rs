@@ -1,4 +1,6 @@
-use kv_memory_vsc_v0::KVMemV0;
+use kv_memory_vsc_v0::{Alibi, KVMemConfig, KVMemV0, MemoryBias, WriteRule};
+
+const ALIBI_SLOPE: f64 = 0.25;
 
 fn max_abs(x: &[f64]) -> f64 {
     x.iter().map(|v| v.abs()).fold(0.0, f64::max)
@@ -27,6 +29,32 @@ fn e(d: usize, idx: usize, s: f64) -> Vec<f64> {
     v
 }
 
+// Fact A written once, then n rounds of a decaying reuse write plus a window
+// eviction before each ask; prints one HIT/MISS per n.
+fn kvmem_run(label: &str, cfg: &KVMemConfig, q_a: &[f64], k_a: &[f64], v_a: &[f64], n_max: usize, thr: f64) -> KVMemV0 {
+    let d = cfg.d;
+    let k_evict = e(d, 0, -1.0);
+    let k_decay = e(d, 0, 1.0);
+    let v_zero = vec![0.0f64; d];
+
+    let mut mem = KVMemV0::from_config(cfg).unwrap();
+    let _ = mem.step(vec![0.0; d], k_a.to_vec(), v_a.to_vec(), true);
+
+    print!("{}:", label);
+    for n in 0..=n_max {
+        if n == 0 {
+            let _ = mem.step(vec![0.0; d], k_evict.clone(), v_zero.clone(), false);
+        } else {
+            let _ = mem.step(vec![0.0; d], k_decay.clone(), v_zero.clone(), true);
+            let _ = mem.step(vec![0.0; d], k_evict.clone(), v_zero.clone(), false);
+        }
+        let out = mem.step(q_a.to_vec(), v_zero.clone(), v_zero.clone(), false);
+        print!(" n{}={}", n, status(&out, 0, thr));
+    }
+    println!();
+    mem
+}
+
 fn main() {
     let (l, d) = (1usize, 8usize);
     let (tau_reuse, tau_novel) = (0.9f64, 0.5f64);
@@ -39,6 +67,15 @@ fn main() {
         l, d, a_amp, thr, tau_reuse, tau_novel, g_write, n_max
     );
 
+    let cfg = KVMemConfig::builder()
+        .l_window(l)
+        .d(d)
+        .tau_reuse(tau_reuse)
+        .tau_novel(tau_novel)
+        .g_write(g_write)
+        .build()
+        .expect("valid config");
+
     let q_a = e(d, 0, 1.0);
     let k_a = e(d, 0, 1.0);
     let v_a = e(d, 0, a_amp);
@@ -48,7 +85,7 @@ fn main() {
     let v_zero  = vec![0.0f64; d];
 
     // Baseline (M=0)
-    let mut base = KVMemV0::new(l, 0, d, tau_reuse, tau_novel, g_write);
+    let mut base = KVMemV0::from_config(&KVMemConfig { m_slots: 0, ..cfg.clone() }).unwrap();
     let _ = base.step(vec![0.0; d], k_a.clone(), v_a.clone(), true);
 
     print!("baseline:");
@@ -66,21 +103,28 @@ fn main() {
     println!("baseline_state_sha256: {}", base.state_sha256());
 
     // KV-mem (M=1)
-    let mut mem = KVMemV0::new(l, 1, d, tau_reuse, tau_novel, g_write);
-    let _ = mem.step(vec![0.0; d], k_a.clone(), v_a, true);
-
-    print!("kvmem_g025:");
-    for n in 0..=n_max {
-        if n == 0 {
-            let _ = mem.step(vec![0.0; d], k_evict.clone(), v_zero.clone(), false);
-        } else {
-            let _ = mem.step(vec![0.0; d], k_decay.clone(), v_zero.clone(), true);
-            let _ = mem.step(vec![0.0; d], k_evict.clone(), v_zero.clone(), false);
-        }
-        let out = mem.step(q_a.clone(), v_zero.clone(), v_zero.clone(), false);
-        print!(" n{}={}", n, status(&out, 0, thr));
-    }
-    println!();
+    let mem = kvmem_run("kvmem_g025", &KVMemConfig { m_slots: 1, ..cfg.clone() }, &q_a, &k_a, &v_a, n_max, thr);
     println!("kvmem_state_sha256: {}", mem.state_sha256());
     println!("kvmem_memory_kv_sha256: {}", mem.memory_kv_sha256());
+
+    // The same decay curve under each write rule; `ema` reproduces kvmem_g025.
+    for (label, write_rule) in [
+        ("ema", WriteRule::Ema),
+        ("overwrite", WriteRule::Overwrite),
+        ("delta", WriteRule::Delta),
+        ("running_mean", WriteRule::RunningMean),
+        ("max_norm", WriteRule::MaxNorm),
+    ] {
+        let cfg = KVMemConfig { m_slots: 1, write_rule, ..cfg.clone() };
+        kvmem_run(&format!("kvmem_g025_rule_{}", label), &cfg, &q_a, &k_a, &v_a, n_max, thr);
+    }
+
+    // ALiBi penalty with the slot exempt vs penalized by its age (L=1, so the
+    // window token is never penalized).
+    println!("alibi_slope: {}", ALIBI_SLOPE);
+    for (label, memory) in [("exempt", MemoryBias::Exempt), ("age", MemoryBias::Age)] {
+        let alibi = Some(Alibi { slope: ALIBI_SLOPE, memory });
+        let cfg = KVMemConfig { m_slots: 1, alibi, ..cfg.clone() };
+        kvmem_run(&format!("kvmem_g025_alibi_{}", label), &cfg, &q_a, &k_a, &v_a, n_max, thr);
+    }
 }
@@ -1,4 +1,4 @@
-use kv_memory_vsc_v0::KVMemV0;
+use kv_memory_vsc_v0::{KVMemConfig, KVMemV0};
 
 const TOK_FACT: usize = 2;
 const TOK_FILL: usize = 1;
@@ -32,18 +32,21 @@ fn run(mut m: KVMemV0, l_fill: usize) -> (String, String) {
 }
 
 fn main() {
-    let l_window = 8usize;
-    let d = 2usize;
     let l_fill = 64usize;
 
-    let tau_reuse = 0.85f64;
-    let tau_novel = 0.50f64;
-    let g_write = 1.0f64;
+    let cfg = KVMemConfig::builder()
+        .l_window(8)
+        .d(2)
+        .tau_reuse(0.85)
+        .tau_novel(0.50)
+        .g_write(1.0)
+        .build()
+        .expect("valid config");
 
-    let base = KVMemV0::new(l_window, 0, d, tau_reuse, tau_novel, g_write);
+    let base = KVMemV0::from_config(&KVMemConfig { m_slots: 0, ..cfg.clone() }).unwrap();
     let (ans_b, h_b) = run(base, l_fill);
 
-    let mem  = KVMemV0::new(l_window, 1, d, tau_reuse, tau_novel, g_write);
+    let mem  = KVMemV0::from_config(&KVMemConfig { m_slots: 1, ..cfg }).unwrap();
     let (ans_m, h_m) = run(mem, l_fill);
 
     println!("baseline: {}", ans_b);
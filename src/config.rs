@@ -0,0 +1,401 @@
+use std::hash::{Hash, Hasher};
+
+use sha2::{Digest, Sha256};
+
+use crate::{Alibi, KVMemError, ReadMode, ReadRefresh, Replacement, Rope, WriteGains, WriteRule};
+
+// Defaults follow the pinned core-demo parameters in vsc/manifest.json.
+pub const DEFAULT_L_WINDOW: usize = 8;
+pub const DEFAULT_M_SLOTS: usize = 1;
+pub const DEFAULT_D: usize = 2;
+pub const DEFAULT_TAU_REUSE: f64 = 0.9;
+pub const DEFAULT_TAU_NOVEL: f64 = 0.5;
+pub const DEFAULT_G_WRITE: f64 = 1.0;
+pub const DEFAULT_READ_HIT_THRESHOLD: f64 = 0.5;
+pub const DEFAULT_DECAY: f64 = 1.0;
+
+/// Static parameters of a `KVMemV0`, independent of its window and memory contents.
+///
+/// Equality and `Hash` compare f64 fields by bit pattern, so two configs are equal
+/// exactly when they produce the same `sha256`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KVMemConfig {
+    pub l_window: usize,
+    pub m_slots: usize,
+    pub d: usize,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub tau_reuse: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub tau_novel: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub g_write: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub replacement: Replacement,
+    /// Minimum softmax weight on a memory slot for a `step` read to count as a hit.
+    #[cfg_attr(feature = "serde", serde(default = "default_read_hit_threshold", with = "crate::repr::f64_str"))]
+    pub read_hit_threshold: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_refresh: ReadRefresh,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_mode: ReadMode,
+    /// Reads attend to at most this many memory slots (highest scores, ties to the
+    /// lowest index); the window is unaffected. `None` reads every slot.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub memory_top_k: Option<usize>,
+    /// Rotary position embedding for reads; `None` leaves keys position-free (v0).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rope: Option<Rope>,
+    /// Linear recency penalty on read logits; `None` applies none (v0).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub alibi: Option<Alibi>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub write_rule: WriteRule,
+    /// Per-path key/value gains replacing `g_write`; `None` uses `g_write` for all (v0).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub write_gains: Option<WriteGains>,
+    /// Per-step factors applied to every slot key and value; 1.0 (default) keeps them.
+    #[cfg_attr(feature = "serde", serde(default = "default_decay", with = "crate::repr::f64_str"))]
+    pub key_decay: f64,
+    #[cfg_attr(feature = "serde", serde(default = "default_decay", with = "crate::repr::f64_str"))]
+    pub value_decay: f64,
+    /// Slots whose age exceeds this many steps are zeroed and become free.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub slot_ttl: Option<u64>,
+}
+
+#[cfg(feature = "serde")]
+fn default_read_hit_threshold() -> f64 {
+    DEFAULT_READ_HIT_THRESHOLD
+}
+
+#[cfg(feature = "serde")]
+fn default_decay() -> f64 {
+    DEFAULT_DECAY
+}
+
+impl Default for KVMemConfig {
+    fn default() -> Self {
+        Self {
+            l_window: DEFAULT_L_WINDOW,
+            m_slots: DEFAULT_M_SLOTS,
+            d: DEFAULT_D,
+            tau_reuse: DEFAULT_TAU_REUSE,
+            tau_novel: DEFAULT_TAU_NOVEL,
+            g_write: DEFAULT_G_WRITE,
+            replacement: Replacement::Lru,
+            read_hit_threshold: DEFAULT_READ_HIT_THRESHOLD,
+            read_refresh: ReadRefresh::Off,
+            read_mode: ReadMode::Joint,
+            memory_top_k: None,
+            rope: None,
+            alibi: None,
+            write_rule: WriteRule::Ema,
+            write_gains: None,
+            key_decay: DEFAULT_DECAY,
+            value_decay: DEFAULT_DECAY,
+            slot_ttl: None,
+        }
+    }
+}
+
+impl KVMemConfig {
+    pub fn builder() -> KVMemConfigBuilder {
+        KVMemConfigBuilder::default()
+    }
+
+    pub fn validate(&self) -> Result<(), KVMemError> {
+        if self.d == 0 {
+            return Err(KVMemError::ZeroDimension);
+        }
+        if self.l_window == 0 && self.m_slots == 0 {
+            return Err(KVMemError::EmptyAttentionDomain);
+        }
+        // Window and slot rows are allocated as n * d scalars of at most f64 size.
+        for (name, n) in [("l_window * d", self.l_window), ("m_slots * d", self.m_slots)] {
+            let bytes = n.checked_mul(self.d).and_then(|x| x.checked_mul(std::mem::size_of::<f64>()));
+            if bytes.is_none_or(|b| b > isize::MAX as usize) {
+                return Err(KVMemError::SizeOverflow { name });
+            }
+        }
+        // NaN fails every range check below, so it is rejected with the same variant.
+        if !(-1.0..=1.0).contains(&self.tau_reuse) {
+            return Err(KVMemError::ThresholdOutOfRange { name: "tau_reuse", value: self.tau_reuse });
+        }
+        if !(-1.0..=1.0).contains(&self.tau_novel) {
+            return Err(KVMemError::ThresholdOutOfRange { name: "tau_novel", value: self.tau_novel });
+        }
+        if !(self.g_write > 0.0 && self.g_write <= 1.0) {
+            return Err(KVMemError::GainOutOfRange { name: "g_write", value: self.g_write });
+        }
+        if !(0.0..=1.0).contains(&self.read_hit_threshold) {
+            return Err(KVMemError::WeightOutOfRange { name: "read_hit_threshold", value: self.read_hit_threshold });
+        }
+        if self.memory_top_k == Some(0) {
+            return Err(KVMemError::ZeroTopK);
+        }
+        match self.read_mode {
+            ReadMode::Joint => {}
+            ReadMode::FixedGate { memory_weight } => {
+                if !(0.0..=1.0).contains(&memory_weight) {
+                    return Err(KVMemError::WeightOutOfRange { name: "memory_weight", value: memory_weight });
+                }
+            }
+            ReadMode::ScoreGate { temperature } => {
+                if !(temperature > 0.0 && temperature.is_finite()) {
+                    return Err(KVMemError::NotPositive { name: "temperature", value: temperature });
+                }
+            }
+        }
+        if let Some(rope) = self.rope {
+            if !(rope.base > 0.0 && rope.base.is_finite()) {
+                return Err(KVMemError::NotPositive { name: "rope_base", value: rope.base });
+            }
+        }
+        if let Some(gains) = self.write_gains {
+            for (name, value) in gains.named() {
+                if !(value > 0.0 && value <= 1.0) {
+                    return Err(KVMemError::GainOutOfRange { name, value });
+                }
+            }
+        }
+        for (name, value) in [("key_decay", self.key_decay), ("value_decay", self.value_decay)] {
+            if !(value > 0.0 && value <= 1.0) {
+                return Err(KVMemError::GainOutOfRange { name, value });
+            }
+        }
+        if self.slot_ttl == Some(0) {
+            return Err(KVMemError::ZeroTtl);
+        }
+        if let Some(alibi) = self.alibi {
+            if !(alibi.slope > 0.0 && alibi.slope.is_finite()) {
+                return Err(KVMemError::NotPositive { name: "alibi_slope", value: alibi.slope });
+            }
+        }
+        Ok(())
+    }
+
+    // Same bytes that prefix state_sha256. Options added after v0 are only hashed
+    // when they differ from their default, so v0 configs keep their v0 hashes.
+    pub(crate) fn hash_into(&self, h: &mut Sha256) {
+        h.update((self.l_window as u64).to_le_bytes());
+        h.update((self.m_slots as u64).to_le_bytes());
+        h.update((self.d as u64).to_le_bytes());
+        h.update(self.tau_reuse.to_le_bytes());
+        h.update(self.tau_novel.to_le_bytes());
+        h.update(self.g_write.to_le_bytes());
+
+        if self.replacement != Replacement::Lru {
+            h.update(b"replacement");
+            h.update([self.replacement.tag()]);
+        }
+        if self.read_hit_threshold.to_bits() != DEFAULT_READ_HIT_THRESHOLD.to_bits() {
+            h.update(b"read_hit_threshold");
+            h.update(self.read_hit_threshold.to_le_bytes());
+        }
+        if self.read_refresh != ReadRefresh::Off {
+            let (tag, n) = self.read_refresh.tag();
+            h.update(b"read_refresh");
+            h.update([tag]);
+            h.update(n.to_le_bytes());
+        }
+        if self.read_mode != ReadMode::Joint {
+            let (tag, x) = self.read_mode.tag();
+            h.update(b"read_mode");
+            h.update([tag]);
+            h.update(x.to_le_bytes());
+        }
+        if let Some(k) = self.memory_top_k {
+            h.update(b"memory_top_k");
+            h.update((k as u64).to_le_bytes());
+        }
+        if self.rope.is_some() {
+            let (tag, base) = Rope::tag(self.rope);
+            h.update(b"rope");
+            h.update([tag]);
+            h.update(base.to_le_bytes());
+        }
+        if self.alibi.is_some() {
+            let (tag, slope) = Alibi::tag(self.alibi);
+            h.update(b"alibi");
+            h.update([tag]);
+            h.update(slope.to_le_bytes());
+        }
+        if self.write_rule != WriteRule::Ema {
+            h.update(b"write_rule");
+            h.update([self.write_rule.tag()]);
+        }
+        if let Some(g) = self.write_gains {
+            h.update(b"write_gains");
+            for (_, x) in g.named() {
+                h.update(x.to_le_bytes());
+            }
+        }
+        for (label, x) in [(&b"key_decay"[..], self.key_decay), (&b"value_decay"[..], self.value_decay)] {
+            if x.to_bits() != DEFAULT_DECAY.to_bits() {
+                h.update(label);
+                h.update(x.to_le_bytes());
+            }
+        }
+        if let Some(ttl) = self.slot_ttl {
+            h.update(b"slot_ttl");
+            h.update(ttl.to_le_bytes());
+        }
+    }
+
+    pub fn sha256(&self) -> String {
+        let mut h = Sha256::new();
+        self.hash_into(&mut h);
+        hex::encode(h.finalize())
+    }
+}
+
+impl PartialEq for KVMemConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.l_window == other.l_window
+            && self.m_slots == other.m_slots
+            && self.d == other.d
+            && self.tau_reuse.to_bits() == other.tau_reuse.to_bits()
+            && self.tau_novel.to_bits() == other.tau_novel.to_bits()
+            && self.g_write.to_bits() == other.g_write.to_bits()
+            && self.replacement == other.replacement
+            && self.read_hit_threshold.to_bits() == other.read_hit_threshold.to_bits()
+            && self.read_refresh == other.read_refresh
+            && self.read_mode.bits() == other.read_mode.bits()
+            && self.memory_top_k == other.memory_top_k
+            && Rope::bits(self.rope) == Rope::bits(other.rope)
+            && Alibi::bits(self.alibi) == Alibi::bits(other.alibi)
+            && self.write_rule == other.write_rule
+            && self.write_gains.map(WriteGains::bits) == other.write_gains.map(WriteGains::bits)
+            && self.key_decay.to_bits() == other.key_decay.to_bits()
+            && self.value_decay.to_bits() == other.value_decay.to_bits()
+            && self.slot_ttl == other.slot_ttl
+    }
+}
+
+impl Eq for KVMemConfig {}
+
+impl Hash for KVMemConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.l_window.hash(state);
+        self.m_slots.hash(state);
+        self.d.hash(state);
+        self.tau_reuse.to_bits().hash(state);
+        self.tau_novel.to_bits().hash(state);
+        self.g_write.to_bits().hash(state);
+        self.replacement.hash(state);
+        self.read_hit_threshold.to_bits().hash(state);
+        self.read_refresh.hash(state);
+        self.read_mode.bits().hash(state);
+        self.memory_top_k.hash(state);
+        Rope::bits(self.rope).hash(state);
+        Alibi::bits(self.alibi).hash(state);
+        self.write_rule.hash(state);
+        self.write_gains.map(WriteGains::bits).hash(state);
+        self.key_decay.to_bits().hash(state);
+        self.value_decay.to_bits().hash(state);
+        self.slot_ttl.hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KVMemConfigBuilder {
+    cfg: KVMemConfig,
+}
+
+impl KVMemConfigBuilder {
+    pub fn l_window(mut self, l_window: usize) -> Self {
+        self.cfg.l_window = l_window;
+        self
+    }
+
+    pub fn m_slots(mut self, m_slots: usize) -> Self {
+        self.cfg.m_slots = m_slots;
+        self
+    }
+
+    pub fn d(mut self, d: usize) -> Self {
+        self.cfg.d = d;
+        self
+    }
+
+    pub fn tau_reuse(mut self, tau_reuse: f64) -> Self {
+        self.cfg.tau_reuse = tau_reuse;
+        self
+    }
+
+    pub fn tau_novel(mut self, tau_novel: f64) -> Self {
+        self.cfg.tau_novel = tau_novel;
+        self
+    }
+
+    pub fn g_write(mut self, g_write: f64) -> Self {
+        self.cfg.g_write = g_write;
+        self
+    }
+
+    pub fn replacement(mut self, replacement: Replacement) -> Self {
+        self.cfg.replacement = replacement;
+        self
+    }
+
+    pub fn read_hit_threshold(mut self, read_hit_threshold: f64) -> Self {
+        self.cfg.read_hit_threshold = read_hit_threshold;
+        self
+    }
+
+    pub fn read_refresh(mut self, read_refresh: ReadRefresh) -> Self {
+        self.cfg.read_refresh = read_refresh;
+        self
+    }
+
+    pub fn read_mode(mut self, read_mode: ReadMode) -> Self {
+        self.cfg.read_mode = read_mode;
+        self
+    }
+
+    pub fn memory_top_k(mut self, memory_top_k: Option<usize>) -> Self {
+        self.cfg.memory_top_k = memory_top_k;
+        self
+    }
+
+    pub fn rope(mut self, rope: Option<Rope>) -> Self {
+        self.cfg.rope = rope;
+        self
+    }
+
+    pub fn alibi(mut self, alibi: Option<Alibi>) -> Self {
+        self.cfg.alibi = alibi;
+        self
+    }
+
+    pub fn write_rule(mut self, write_rule: WriteRule) -> Self {
+        self.cfg.write_rule = write_rule;
+        self
+    }
+
+    pub fn write_gains(mut self, write_gains: Option<WriteGains>) -> Self {
+        self.cfg.write_gains = write_gains;
+        self
+    }
+
+    pub fn key_decay(mut self, key_decay: f64) -> Self {
+        self.cfg.key_decay = key_decay;
+        self
+    }
+
+    pub fn value_decay(mut self, value_decay: f64) -> Self {
+        self.cfg.value_decay = value_decay;
+        self
+    }
+
+    pub fn slot_ttl(mut self, slot_ttl: Option<u64>) -> Self {
+        self.cfg.slot_ttl = slot_ttl;
+        self
+    }
+
+    pub fn build(self) -> Result<KVMemConfig, KVMemError> {
+        self.cfg.validate()?;
+        Ok(self.cfg)
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KVMemError {
+    ZeroDimension,
+    ZeroHeads,
+    HeadGrouping { n_heads: usize, n_kv_heads: usize },
+    EmptyAttentionDomain,
+    SizeOverflow { name: &'static str },
+    WritePosition { slot: usize, write_pos: u64, position: u64 },
+    ZeroTopK,
+    ZeroTtl,
+    ThresholdOutOfRange { name: &'static str, value: f64 },
+    GainOutOfRange { name: &'static str, value: f64 },
+    WeightOutOfRange { name: &'static str, value: f64 },
+    NotPositive { name: &'static str, value: f64 },
+    DimensionMismatch { name: &'static str, expected: usize, got: usize },
+    NonFiniteInput { name: &'static str, index: usize },
+    SpecMismatch(String),
+    #[cfg(feature = "ann")]
+    IndexConfig(&'static str),
+}
+
+impl fmt::Display for KVMemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KVMemError::ZeroDimension => write!(f, "d must be > 0"),
+            KVMemError::ZeroHeads => write!(f, "n_heads must be > 0"),
+            KVMemError::HeadGrouping { n_heads, n_kv_heads } => {
+                write!(f, "n_heads = {} is not a positive multiple of n_kv_heads = {}", n_heads, n_kv_heads)
+            }
+            KVMemError::EmptyAttentionDomain => {
+                write!(f, "l_window and m_slots are both 0: attention domain is empty")
+            }
+            KVMemError::SizeOverflow { name } => write!(f, "{} is too large to allocate", name),
+            KVMemError::WritePosition { slot, write_pos, position } => write!(
+                f,
+                "write_pos[{}] = {} at position {}: an occupied slot's must be below position, a free slot's 0",
+                slot, write_pos, position
+            ),
+            KVMemError::ZeroTopK => write!(f, "memory_top_k must be > 0 (use None to read every slot)"),
+            KVMemError::ZeroTtl => write!(f, "slot_ttl must be > 0 (use None to keep slots until overwritten)"),
+            KVMemError::ThresholdOutOfRange { name, value } => {
+                write!(f, "{} = {} is outside [-1, 1]", name, value)
+            }
+            KVMemError::GainOutOfRange { name, value } => {
+                write!(f, "{} = {} is outside (0, 1]", name, value)
+            }
+            KVMemError::WeightOutOfRange { name, value } => {
+                write!(f, "{} = {} is outside [0, 1]", name, value)
+            }
+            KVMemError::NotPositive { name, value } => {
+                write!(f, "{} = {} must be finite and > 0", name, value)
+            }
+            KVMemError::DimensionMismatch { name, expected, got } => {
+                write!(f, "{} has length {}, expected {}", name, got, expected)
+            }
+            KVMemError::NonFiniteInput { name, index } => {
+                write!(f, "{}[{}] is not finite", name, index)
+            }
+            KVMemError::SpecMismatch(s) => write!(f, "spec id {:?} != {:?}", s, crate::SPEC_ID),
+            #[cfg(feature = "ann")]
+            KVMemError::IndexConfig(what) => write!(f, "invalid index config: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for KVMemError {}
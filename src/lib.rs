@@ -1,54 +1,325 @@
+use std::sync::Arc;
+
 use sha2::{Digest, Sha256};
 
+#[cfg(feature = "ann")]
+mod ann;
+mod config;
+mod error;
+mod multihead;
+mod observer;
+mod outcome;
+mod policy;
+mod position;
+mod read_mode;
+#[cfg(feature = "serde")]
+mod repr;
+mod scalar;
+mod snapshot;
+mod state;
+mod stats;
+mod trace;
+mod write_rule;
+
+#[cfg(feature = "ann")]
+pub use ann::LshConfig;
+pub use config::{
+    KVMemConfig, KVMemConfigBuilder, DEFAULT_D, DEFAULT_DECAY, DEFAULT_G_WRITE, DEFAULT_L_WINDOW, DEFAULT_M_SLOTS,
+    DEFAULT_READ_HIT_THRESHOLD, DEFAULT_TAU_NOVEL, DEFAULT_TAU_REUSE,
+};
+pub use error::KVMemError;
+pub use multihead::MultiHeadKVMem;
+pub use observer::MemoryObserver;
+pub use outcome::WriteOutcome;
+pub use policy::{Fifo, LeastAttended, Lfu, LowestNorm, Lru, ReadRefresh, Replacement, ReplacementPolicy, SlotMeta};
+pub use position::{Alibi, MemoryBias, MemoryPosition, Rope};
+pub use read_mode::ReadMode;
+pub use scalar::Scalar;
+pub use snapshot::{SnapshotError, SNAPSHOT_MAGIC, SNAPSHOT_VERSION};
+pub use state::KVMemState;
+pub use stats::KVMemStats;
+pub use trace::{AttentionTrace, KeyOrigin, StepTrace};
+pub use write_rule::{WriteGains, WriteRule};
+
+pub const SPEC_ID: &str = "kv-memory/v0";
+
+/// Sliding-window attention over window + persistent KV slots.
+///
+/// `S` is the element type of inputs, outputs and stored rows (see `Scalar`);
+/// snapshots, `KVMemState` and `MultiHeadKVMem` use the default `f64`.
 #[derive(Clone)]
-pub struct KVMemV0 {
+pub struct KVMemV0<S: Scalar = f64> {
     pub l_window: usize,
     pub m_slots: usize,
     pub d: usize,
     pub tau_reuse: f64,
     pub tau_novel: f64,
     pub g_write: f64,
-
-    kw: Vec<Vec<f64>>,
-    vw: Vec<Vec<f64>>,
-
-    km: Vec<Vec<f64>>,
-    vm: Vec<Vec<f64>>,
+    pub replacement: Replacement,
+    pub read_hit_threshold: f64,
+    pub read_refresh: ReadRefresh,
+    pub read_mode: ReadMode,
+    pub memory_top_k: Option<usize>,
+    pub rope: Option<Rope>,
+    pub alibi: Option<Alibi>,
+    pub write_rule: WriteRule,
+    pub write_gains: Option<WriteGains>,
+    pub key_decay: f64,
+    pub value_decay: f64,
+    pub slot_ttl: Option<u64>,
+
+    // Window: ring buffer of l_window rows of d, logical row i (0 = oldest) at
+    // physical row (w_head + i) % l_window.
+    kw: Vec<S>,
+    vw: Vec<S>,
+    w_head: usize,
+    w_len: usize,
+
+    // Memory: m_slots rows of d, row-major.
+    km: Vec<S>,
+    vm: Vec<S>,
     age: Vec<u64>,
+    // Slots holding a written key/value. Unoccupied slots are skipped by reads
+    // and `max_sim` and filled first by novel writes.
+    occupied: Vec<bool>,
+    // Writes since each slot's last novel write (including it); read by `WriteRule::RunningMean`.
+    write_count: Vec<u64>,
+
+    // Tokens ingested so far (the next token's position), and each slot's
+    // position at its last write; read only by `MemoryPosition::WritePosition`.
+    position: u64,
+    write_pos: Vec<u64>,
+
+    // Replacement-policy bookkeeping; only hashed when a non-LRU policy reads it.
+    // read_hits counts `step` reads giving a slot >= read_hit_threshold weight.
+    insert_age: Vec<u64>,
+    read_hits: Vec<u64>,
+    attention: Vec<f64>,
+
+    // Attention scores/weights and top-k slot order for `step_into`, sized
+    // l_window + m_slots and m_slots.
+    scratch: Vec<f64>,
+    slot_order: Vec<usize>,
+    // Slot weights and prediction of `WriteRule::Delta`, sized m_slots + d.
+    delta_scratch: Vec<f64>,
+    // LSH bucket candidates for the write path's `max_sim`; unused without an index.
+    candidates: Vec<usize>,
+
+    observer: Option<Arc<dyn MemoryObserver<S>>>,
+    policy: Option<Arc<dyn ReplacementPolicy + Send + Sync>>,
+    stats: KVMemStats,
+    #[cfg(feature = "ann")]
+    index: Option<ann::LshIndex>,
+}
+
+// `vec![x; n]`, failing with `SizeOverflow` instead of aborting when the
+// allocation fails: sizes may come from an untrusted snapshot or state.
+fn try_vec<T: Clone>(name: &'static str, x: T, n: usize) -> Result<Vec<T>, KVMemError> {
+    let mut v = Vec::new();
+    v.try_reserve_exact(n).map_err(|_| KVMemError::SizeOverflow { name })?;
+    v.resize(n, x);
+    Ok(v)
 }
 
 impl KVMemV0 {
     pub fn new(l_window: usize, m_slots: usize, d: usize, tau_reuse: f64, tau_novel: f64, g_write: f64) -> Self {
-        assert!(d > 0);
-        assert!(tau_reuse >= -1.0 && tau_reuse <= 1.0);
-        assert!(tau_novel >= -1.0 && tau_novel <= 1.0);
-        assert!(g_write > 0.0 && g_write <= 1.0);
+        Self::try_new(l_window, m_slots, d, tau_reuse, tau_novel, g_write)
+            .unwrap_or_else(|e| panic!("KVMemV0::new: {}", e))
+    }
+
+    pub fn try_new(
+        l_window: usize,
+        m_slots: usize,
+        d: usize,
+        tau_reuse: f64,
+        tau_novel: f64,
+        g_write: f64,
+    ) -> Result<Self, KVMemError> {
+        Self::from_config(&KVMemConfig { l_window, m_slots, d, tau_reuse, tau_novel, g_write, ..KVMemConfig::default() })
+    }
+
+    pub fn from_config(cfg: &KVMemConfig) -> Result<Self, KVMemError> {
+        Self::with_config(cfg)
+    }
+}
+
+impl<S: Scalar> KVMemV0<S> {
+    /// `from_config` for any scalar type, e.g. `KVMemV0::<f32>::with_config(&cfg)`.
+    pub fn with_config(cfg: &KVMemConfig) -> Result<Self, KVMemError> {
+        cfg.validate()?;
+
+        Ok(Self {
+            l_window: cfg.l_window,
+            m_slots: cfg.m_slots,
+            d: cfg.d,
+            tau_reuse: cfg.tau_reuse,
+            tau_novel: cfg.tau_novel,
+            g_write: cfg.g_write,
+            replacement: cfg.replacement,
+            read_hit_threshold: cfg.read_hit_threshold,
+            read_refresh: cfg.read_refresh,
+            read_mode: cfg.read_mode,
+            memory_top_k: cfg.memory_top_k,
+            rope: cfg.rope,
+            alibi: cfg.alibi,
+            write_rule: cfg.write_rule,
+            write_gains: cfg.write_gains,
+            key_decay: cfg.key_decay,
+            value_decay: cfg.value_decay,
+            slot_ttl: cfg.slot_ttl,
+            kw: try_vec("l_window * d", S::default(), cfg.l_window * cfg.d)?,
+            vw: try_vec("l_window * d", S::default(), cfg.l_window * cfg.d)?,
+            w_head: 0,
+            w_len: 0,
+            km: try_vec("m_slots * d", S::default(), cfg.m_slots * cfg.d)?,
+            vm: try_vec("m_slots * d", S::default(), cfg.m_slots * cfg.d)?,
+            age: try_vec("m_slots", 0, cfg.m_slots)?,
+            occupied: try_vec("m_slots", false, cfg.m_slots)?,
+            write_count: try_vec("m_slots", 0, cfg.m_slots)?,
+            position: 0,
+            write_pos: try_vec("m_slots", 0, cfg.m_slots)?,
+            insert_age: try_vec("m_slots", 0, cfg.m_slots)?,
+            read_hits: try_vec("m_slots", 0, cfg.m_slots)?,
+            attention: try_vec("m_slots", 0.0, cfg.m_slots)?,
+            scratch: try_vec("l_window + m_slots", 0.0, cfg.l_window + cfg.m_slots)?,
+            slot_order: try_vec("m_slots", 0, cfg.m_slots)?,
+            delta_scratch: try_vec("m_slots + d", 0.0, cfg.m_slots + cfg.d)?,
+            candidates: Vec::new(),
+            observer: None,
+            policy: None,
+            stats: KVMemStats::default(),
+            #[cfg(feature = "ann")]
+            index: None,
+        })
+    }
 
-        Self {
-            l_window,
-            m_slots,
-            d,
-            tau_reuse,
-            tau_novel,
-            g_write,
-            kw: vec![],
-            vw: vec![],
-            km: vec![vec![0.0; d]; m_slots],
-            vm: vec![vec![0.0; d]; m_slots],
-            age: vec![0; m_slots],
+    pub fn config(&self) -> KVMemConfig {
+        KVMemConfig {
+            l_window: self.l_window,
+            m_slots: self.m_slots,
+            d: self.d,
+            tau_reuse: self.tau_reuse,
+            tau_novel: self.tau_novel,
+            g_write: self.g_write,
+            replacement: self.replacement,
+            read_hit_threshold: self.read_hit_threshold,
+            read_refresh: self.read_refresh,
+            read_mode: self.read_mode,
+            memory_top_k: self.memory_top_k,
+            rope: self.rope,
+            alibi: self.alibi,
+            write_rule: self.write_rule,
+            write_gains: self.write_gains,
+            key_decay: self.key_decay,
+            value_decay: self.value_decay,
+            slot_ttl: self.slot_ttl,
         }
     }
 
-    fn dot(a: &[f64], b: &[f64]) -> f64 {
-        a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    /// Per-slot count of `step` reads that gave the slot at least `read_hit_threshold` weight.
+    pub fn read_hits(&self) -> &[u64] {
+        &self.read_hits
+    }
+
+    /// Per-slot steps since last write (or refreshing read, see `ReadRefresh`).
+    pub fn slot_ages(&self) -> &[u64] {
+        &self.age
+    }
+
+    /// Per-slot occupancy: true once written, false again after `slot_ttl` expiry.
+    pub fn slot_occupancy(&self) -> &[bool] {
+        &self.occupied
+    }
+
+    /// Per-slot writes (novel and reuse) since the slot's last novel write, including it.
+    pub fn slot_write_counts(&self) -> &[u64] {
+        &self.write_count
+    }
+
+    /// Number of tokens ingested so far, i.e. the position of the next token.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Per-slot token position of the last write (novel or reuse); 0 if never written.
+    pub fn slot_write_positions(&self) -> &[u64] {
+        &self.write_pos
+    }
+
+    /// Registers `observer` for lifecycle events, replacing any previous one.
+    pub fn set_observer(&mut self, observer: Arc<dyn MemoryObserver<S>>) {
+        self.observer = Some(observer);
+    }
+
+    pub fn clear_observer(&mut self) {
+        self.observer = None;
+    }
+
+    /// Picks eviction victims with `policy` in place of the configured
+    /// `replacement`. Like an observer the policy is not hashed, snapshotted or
+    /// serialized, so it must be set again after a restore; while set, the
+    /// per-slot replacement counters are part of `state_sha256`.
+    pub fn set_replacement_policy(&mut self, policy: Arc<dyn ReplacementPolicy + Send + Sync>) {
+        self.policy = Some(policy);
+    }
+
+    /// Returns to the configured `replacement`.
+    pub fn clear_replacement_policy(&mut self) {
+        self.policy = None;
+    }
+
+    pub fn stats(&self) -> &KVMemStats {
+        &self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = KVMemStats::default();
+    }
+
+    /// Number of tokens currently in the window (at most `l_window`).
+    pub fn window_len(&self) -> usize {
+        self.w_len
+    }
+
+    // Logical window row i, 0 = oldest.
+    pub(crate) fn kw_row(&self, i: usize) -> &[S] {
+        let r = (self.w_head + i) % self.l_window;
+        &self.kw[r * self.d..(r + 1) * self.d]
+    }
+
+    pub(crate) fn vw_row(&self, i: usize) -> &[S] {
+        let r = (self.w_head + i) % self.l_window;
+        &self.vw[r * self.d..(r + 1) * self.d]
+    }
+
+    pub(crate) fn km_row(&self, i: usize) -> &[S] {
+        &self.km[i * self.d..(i + 1) * self.d]
+    }
+
+    pub(crate) fn vm_row(&self, i: usize) -> &[S] {
+        &self.vm[i * self.d..(i + 1) * self.d]
     }
 
-    fn norm(a: &[f64]) -> f64 {
+    fn check_input(&self, name: &'static str, x: &[S]) -> Result<(), KVMemError> {
+        if x.len() != self.d {
+            return Err(KVMemError::DimensionMismatch { name, expected: self.d, got: x.len() });
+        }
+        match x.iter().position(|v| !v.to_f64().is_finite()) {
+            Some(index) => Err(KVMemError::NonFiniteInput { name, index }),
+            None => Ok(()),
+        }
+    }
+
+    fn dot(a: &[S], b: &[S]) -> f64 {
+        a.iter().zip(b.iter()).map(|(x, y)| x.to_f64() * y.to_f64()).sum()
+    }
+
+    fn norm(a: &[S]) -> f64 {
         Self::dot(a, a).sqrt()
     }
 
-    fn cosine(a: &[f64], b: &[f64]) -> f64 {
-        if a.iter().any(|x| x.is_nan()) || b.iter().any(|x| x.is_nan()) {
+    fn cosine(a: &[S], b: &[S]) -> f64 {
+        if a.iter().any(|x| x.to_f64().is_nan()) || b.iter().any(|x| x.to_f64().is_nan()) {
             return f64::NEG_INFINITY;
         }
         let na = Self::norm(a);
@@ -60,46 +331,70 @@ impl KVMemV0 {
         }
     }
 
-    fn softmax(scores: &[f64]) -> Vec<f64> {
-        let m = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-        let exps: Vec<f64> = scores.iter().map(|s| (s - m).exp()).collect();
-        let z: f64 = exps.iter().sum();
+    fn softmax_in_place(s: &mut [f64]) {
+        let m = s.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        for x in s.iter_mut() {
+            *x = (*x - m).exp();
+        }
+        let z: f64 = s.iter().sum();
         if z == 0.0 || z.is_nan() {
-            let n = scores.len().max(1) as f64;
-            return vec![1.0 / n; scores.len()];
+            let n = s.len().max(1) as f64;
+            s.fill(1.0 / n);
+            return;
+        }
+        for x in s.iter_mut() {
+            *x /= z;
         }
-        exps.into_iter().map(|e| e / z).collect()
     }
 
-    fn weighted_sum(w: &[f64], vecs: &[Vec<f64>]) -> Vec<f64> {
-        let d = vecs[0].len();
-        let mut out = vec![0.0; d];
-        for (wi, v) in w.iter().zip(vecs.iter()) {
-            for j in 0..d {
-                out[j] += wi * v[j];
-            }
+    // Overwrites the oldest row once the window is full; no memory is moved.
+    pub(crate) fn push_window(&mut self, k: &[S], v: &[S]) {
+        if self.l_window == 0 {
+            return;
         }
-        out
+        let r = if self.w_len < self.l_window {
+            self.w_len += 1;
+            (self.w_head + self.w_len - 1) % self.l_window
+        } else {
+            if let Some(o) = &self.observer {
+                o.on_window_evict(self.kw_row(0), self.vw_row(0));
+            }
+            let r = self.w_head;
+            self.w_head = (self.w_head + 1) % self.l_window;
+            r
+        };
+        let d = self.d;
+        self.kw[r * d..(r + 1) * d].copy_from_slice(k);
+        self.vw[r * d..(r + 1) * d].copy_from_slice(v);
     }
 
-    fn push_window(&mut self, k: Vec<f64>, v: Vec<f64>) {
-        self.kw.push(k);
-        self.vw.push(v);
-        if self.kw.len() > self.l_window {
-            self.kw.remove(0);
-            self.vw.remove(0);
+    // Best cosine over occupied slots; (0, -inf) when none is occupied.
+    // `candidates` is scratch for the LSH index's bucket lookup.
+    fn max_sim(&self, k: &[S], candidates: &mut Vec<usize>) -> (usize, f64) {
+        #[cfg(feature = "ann")]
+        if let Some(ix) = &self.index {
+            ix.candidates_into(k, candidates);
+            candidates.retain(|&j| self.occupied[j]);
+            if !candidates.is_empty() {
+                return self.max_sim_over(k, candidates.iter().copied());
+            }
         }
+        #[cfg(not(feature = "ann"))]
+        let _ = candidates;
+        self.max_sim_over(k, self.occupied_slots())
     }
 
-    fn max_sim(&self, k: &[f64]) -> (usize, f64) {
-        if self.m_slots == 0 {
-            return (0, f64::NEG_INFINITY);
-        }
+    pub(crate) fn occupied_slots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.m_slots).filter(|&j| self.occupied[j])
+    }
 
-        let mut best_i = 0usize;
+    // Best cosine over `slots` (ascending); ties go to the first.
+    fn max_sim_over(&self, k: &[S], slots: impl IntoIterator<Item = usize>) -> (usize, f64) {
+        let mut slots = slots.into_iter().peekable();
+        let mut best_i = slots.peek().copied().unwrap_or(0);
         let mut best_s = f64::NEG_INFINITY;
-        for i in 0..self.m_slots {
-            let s = Self::cosine(k, &self.km[i]);
+        for i in slots {
+            let s = Self::cosine(k, self.km_row(i));
             if s > best_s {
                 best_s = s;
                 best_i = i;
@@ -108,119 +403,496 @@ impl KVMemV0 {
         (best_i, best_s)
     }
 
-    fn choose_slot(&self, k: &[f64]) -> usize {
-        if self.m_slots == 0 {
-            return 0;
+    /// Most similar occupied memory slot to `k` and its cosine similarity, as the
+    /// write path sees it (through the LSH index when one is enabled).
+    pub fn nearest_slot(&self, k: &[S]) -> Option<(usize, f64)> {
+        self.occupied.contains(&true).then(|| self.max_sim(k, &mut Vec::new()))
+    }
+
+    // Keeps the optional slot index in sync after key row j changes.
+    fn reindex(&mut self, j: usize) {
+        #[cfg(feature = "ann")]
+        if let Some(ix) = &mut self.index {
+            let d = self.d;
+            ix.update(j, &self.km[j * d..(j + 1) * d]);
         }
+        #[cfg(not(feature = "ann"))]
+        let _ = j;
+    }
 
-        let (best_i, best_s) = self.max_sim(k);
+    // Memory-only attention readout for query k over occupied slots: the delta
+    // rule's prediction, into buf[m_slots..]; buf[..m_slots] receives the slot
+    // weights. Zero when no slot is occupied.
+    fn memory_read_into(&self, k: &[S], buf: &mut [f64]) {
+        let (w, read) = buf.split_at_mut(self.m_slots);
+        read.fill(0.0);
+        if !self.occupied.contains(&true) {
+            return;
+        }
+        let scale = self.attention_scale();
+        for (j, x) in w.iter_mut().enumerate() {
+            *x = if self.occupied[j] { Self::dot(k, self.km_row(j)) / scale } else { f64::NEG_INFINITY };
+        }
+        Self::softmax_in_place(w);
+        for (j, &wj) in w.iter().enumerate() {
+            for (r, x) in read.iter_mut().zip(self.vm_row(j)) {
+                *r += wj * x.to_f64();
+            }
+        }
+    }
 
-        if best_s >= self.tau_reuse {
-            return best_i;
+    // Folds k, v into slot j by `write_rule`, with the gains of the novel or reuse path.
+    fn write_slot(&mut self, j: usize, k: &[S], v: &[S], novel: bool) {
+        let delta = self.write_rule == WriteRule::Delta;
+        let mut buf = std::mem::take(&mut self.delta_scratch);
+        if delta {
+            self.memory_read_into(k, &mut buf);
         }
+        self.write_count[j] += 1;
+        let (gk, gv) = self.write_gains.map_or((self.g_write, self.g_write), |g| g.for_path(novel));
+        let (d, m, n) = (self.d, self.m_slots, self.write_count[j]);
+        let r = j * d..(j + 1) * d;
+        self.write_rule.update(&mut self.km[r.clone()], k, gk, n, None);
+        self.write_rule.update(&mut self.vm[r], v, gv, n, delta.then(|| &buf[m..]));
+        self.delta_scratch = buf;
+        self.reindex(j);
+    }
 
-        let mut j = 0usize;
-        let mut best_age = self.age[0];
-        for i in 1..self.m_slots {
-            if self.age[i] > best_age {
-                best_age = self.age[i];
-                j = i;
+    // Per-step key/value decay, then expiry of slots older than `slot_ttl`.
+    // Uniform positive scaling keeps a key's LSH codes, so only expiry reindexes.
+    fn decay_and_expire(&mut self) {
+        if self.key_decay != DEFAULT_DECAY {
+            for x in self.km.iter_mut() {
+                *x = S::from_f64(x.to_f64() * self.key_decay);
+            }
+        }
+        if self.value_decay != DEFAULT_DECAY {
+            for x in self.vm.iter_mut() {
+                *x = S::from_f64(x.to_f64() * self.value_decay);
             }
         }
+        let Some(ttl) = self.slot_ttl else { return };
+        for j in 0..self.m_slots {
+            if self.age[j] <= ttl || !self.occupied[j] {
+                continue;
+            }
+            self.stats.expirations += 1;
+            if let Some(o) = &self.observer {
+                o.on_expire(j, self.km_row(j), self.vm_row(j), self.age[j]);
+            }
+            let r = j * self.d..(j + 1) * self.d;
+            self.km[r.clone()].fill(S::default());
+            self.vm[r].fill(S::default());
+            self.occupied[j] = false;
+            self.write_pos[j] = 0;
+            self.reindex(j);
+        }
+    }
+
+    // Unoccupied slots first, lowest index; otherwise the replacement policy's pick.
+    fn victim(&self) -> usize {
+        if let Some(j) = self.occupied.iter().position(|&o| !o) {
+            return j;
+        }
+        let meta = SlotMeta {
+            d: self.d,
+            keys: policy::Keys::Stored(&self.km),
+            age: &self.age,
+            insert_age: &self.insert_age,
+            read_hits: &self.read_hits,
+            attention: &self.attention,
+        };
+        let j = match &self.policy {
+            Some(p) => p.victim(&meta),
+            None => self.replacement.victim(&meta),
+        };
+        assert!(j < self.m_slots, "replacement policy chose slot {} of {}", j, self.m_slots);
         j
     }
 
-    fn write_memory_novelty_gated(&mut self, k: Vec<f64>, v: Vec<f64>, write_event: bool) {
+    fn write_memory_novelty_gated(&mut self, k: &[S], v: &[S], write_event: bool) -> WriteOutcome {
         if self.m_slots == 0 {
-            return;
+            return WriteOutcome::NoMemory;
         }
 
         for i in 0..self.m_slots {
             self.age[i] += 1;
+            self.insert_age[i] += 1;
         }
+        self.decay_and_expire();
 
         if !write_event {
-            return;
+            return WriteOutcome::NoWrite;
         }
 
-        let (best_i, best_s) = self.max_sim(&k);
+        let mut candidates = std::mem::take(&mut self.candidates);
+        let (best_i, best_s) = self.max_sim(k, &mut candidates);
+        self.candidates = candidates;
+        self.stats.record_similarity(best_s);
 
         // REUSE WRITES: if similarity is high, update that slot (must mutate memory_kv_sha256)
         if best_s >= self.tau_reuse {
-            let g = self.g_write;
-            for t in 0..self.d {
-                self.km[best_i][t] = (1.0 - g) * self.km[best_i][t] + g * k[t];
-                self.vm[best_i][t] = (1.0 - g) * self.vm[best_i][t] + g * v[t];
-            }
+            self.write_slot(best_i, k, v, false);
             self.age[best_i] = 0;
-            return;
+            self.write_pos[best_i] = self.position;
+            self.stats.reuse_updates += 1;
+            if let Some(o) = &self.observer {
+                o.on_reuse(best_i, best_s, k, v);
+            }
+            return WriteOutcome::Reused { slot: best_i, similarity: best_s };
         }
 
         // NOVELTY GATE: blocks new writes when too similar (but below reuse threshold)
         if best_s >= self.tau_novel {
-            return;
+            self.stats.gated += 1;
+            if let Some(o) = &self.observer {
+                o.on_gate(best_i, best_s);
+            }
+            return WriteOutcome::Gated { slot: best_i, similarity: best_s };
         }
 
-        let j = self.choose_slot(&k);
-
-        let g = self.g_write;
-        for t in 0..self.d {
-            self.km[j][t] = (1.0 - g) * self.km[j][t] + g * k[t];
-            self.vm[j][t] = (1.0 - g) * self.vm[j][t] + g * v[t];
+        let j = self.victim();
+        let evicted_age = self.occupied[j].then_some(self.age[j]);
+        if let Some(age) = evicted_age {
+            self.stats.evictions += 1;
+            if let Some(o) = &self.observer {
+                o.on_evict(j, self.km_row(j), self.vm_row(j), age);
+            }
         }
+
+        self.write_count[j] = 0;
+        // A victim is still occupied here, so the delta rule's prediction covers
+        // its old key and value, which its row update also starts from. A free
+        // slot joins the prediction only once written.
+        self.write_slot(j, k, v, true);
+        self.occupied[j] = true;
         self.age[j] = 0;
+        self.write_pos[j] = self.position;
+        self.insert_age[j] = 0;
+        self.read_hits[j] = 0;
+        self.attention[j] = 0.0;
+        self.stats.novel_writes += 1;
+        if let Some(o) = &self.observer {
+            o.on_write(j, k, v);
+        }
+        WriteOutcome::Written { slot: j, similarity: best_s, evicted_age }
     }
 
-    pub fn step(&mut self, q: Vec<f64>, k: Vec<f64>, v: Vec<f64>, write_event: bool) -> Vec<f64> {
-        self.write_memory_novelty_gated(k.clone(), v.clone(), write_event);
+    /// Write half of `step`: the memory write path (reuse, novelty gate or novel
+    /// write when `write_event`), then the window push. Produces no output.
+    /// Inputs are not validated; see `try_ingest`.
+    pub fn ingest(&mut self, k: &[S], v: &[S], write_event: bool) -> WriteOutcome {
+        self.stats.steps += 1;
+        self.stats.write_events += write_event as u64;
+        let outcome = self.write_memory_novelty_gated(k, v, write_event);
         self.push_window(k, v);
+        self.position += 1;
+        outcome
+    }
+
+    /// `ingest`, rejecting wrong-length or non-finite `k`/`v` without mutating anything.
+    pub fn try_ingest(&mut self, k: &[S], v: &[S], write_event: bool) -> Result<WriteOutcome, KVMemError> {
+        self.check_input("k", k)?;
+        self.check_input("v", v)?;
+        Ok(self.ingest(k, v, write_event))
+    }
+
+    // Key i of the attention domain: window oldest-to-newest, then slots.
+    fn domain_key(&self, i: usize) -> &[S] {
+        if i < self.w_len { self.kw_row(i) } else { self.km_row(i - self.w_len) }
+    }
+
+    fn domain_value(&self, i: usize) -> &[S] {
+        if i < self.w_len { self.vw_row(i) } else { self.vm_row(i - self.w_len) }
+    }
+
+    pub(crate) fn domain_len(&self) -> usize {
+        self.w_len + self.m_slots
+    }
+
+    pub(crate) fn attention_scale(&self) -> f64 {
+        (self.d as f64).sqrt()
+    }
+
+    // Raw scores q . k over the attention domain, with keys rotated relative to
+    // the query (at the newest window position) when `rope` is set.
+    pub(crate) fn scores_into(&self, q: &[S], w: &mut [f64]) {
+        let Some(rope) = self.rope else {
+            for (i, s) in w.iter_mut().enumerate() {
+                *s = Self::dot(q, self.domain_key(i));
+            }
+            return;
+        };
+        let (window, memory) = w.split_at_mut(self.w_len);
+        for (i, s) in window.iter_mut().enumerate() {
+            *s = rope.score(q, self.kw_row(i), (self.w_len - 1 - i) as f64);
+        }
+        for (j, s) in memory.iter_mut().enumerate() {
+            *s = match rope.memory {
+                MemoryPosition::Free => Self::dot(q, self.km_row(j)),
+                // The query sits at position - 1; unoccupied (masked) slots may give -1.
+                MemoryPosition::WritePosition => {
+                    rope.score(q, self.km_row(j), self.position.saturating_sub(self.write_pos[j]) as f64 - 1.0)
+                }
+            };
+        }
+    }
+
+    // Drops all but the `memory_top_k` highest-scoring slots from the read by
+    // setting their logits to -inf. Ties rank the lowest index first.
+    fn mask_memory_top_k(&self, memory: &mut [f64], order: &mut Vec<usize>) {
+        let k = match self.memory_top_k {
+            Some(k) if k < memory.len() => k,
+            _ => return,
+        };
+        order.clear();
+        order.extend(0..memory.len());
+        order.select_nth_unstable_by(k, |&i, &j| memory[j].total_cmp(&memory[i]).then(i.cmp(&j)));
+        for &i in &order[k..] {
+            memory[i] = f64::NEG_INFINITY;
+        }
+    }
+
+    // Softmax of score / scale less any `alibi` penalty, in place: one softmax
+    // over the whole domain, or per `read_mode` separate window and memory
+    // softmaxes mixed by a gate. Unoccupied slots get zero weight, all slots when
+    // none is occupied. `order` is scratch for `memory_top_k`.
+    pub(crate) fn weights_in_place(&self, w: &mut [f64], order: &mut Vec<usize>, alibi: Option<Alibi>) {
+        let scale = self.attention_scale();
+        for s in w.iter_mut() {
+            *s /= scale;
+        }
+        if let Some(alibi) = alibi {
+            let (window, memory) = w.split_at_mut(self.w_len);
+            alibi.apply(window, memory, &self.age);
+        }
+        for (x, &o) in w[self.w_len..].iter_mut().zip(&self.occupied) {
+            if !o {
+                *x = f64::NEG_INFINITY;
+            }
+        }
+        self.mask_memory_top_k(&mut w[self.w_len..], order);
+        let any_occupied = self.occupied.contains(&true);
+        if self.read_mode == ReadMode::Joint {
+            // With an empty window too, the all -inf softmax would fall back to uniform.
+            Self::softmax_in_place(w);
+            if !any_occupied {
+                w[self.w_len..].fill(0.0);
+            }
+            return;
+        }
+
+        let (window, memory) = w.split_at_mut(self.w_len);
+        let g = if !any_occupied {
+            0.0
+        } else if window.is_empty() {
+            1.0
+        } else {
+            self.read_mode.memory_weight(window, memory)
+        };
+        Self::softmax_in_place(window);
+        Self::softmax_in_place(memory);
+        for x in window.iter_mut() {
+            *x *= 1.0 - g;
+        }
+        for x in memory.iter_mut() {
+            *x *= g;
+        }
+    }
+
+    pub(crate) fn mix_into(&self, w: &[f64], out: &mut [S]) {
+        // Accumulate each output coordinate in f64 over the domain in order.
+        for (j, o) in out.iter_mut().enumerate() {
+            let mut acc = 0.0;
+            for (i, &wi) in w.iter().enumerate() {
+                acc += wi * self.domain_value(i)[j].to_f64();
+            }
+            *o = S::from_f64(acc);
+        }
+    }
 
-        let mut keys = self.kw.clone();
-        let mut vals = self.vw.clone();
+    // Read half of `step`: softmax attention over window + memory slots, with
+    // `alibi` in place of the configured one (per-head slopes in `MultiHeadKVMem`).
+    // `w` receives the weights and needs at least `domain_len()` entries.
+    fn attend_into(&self, q: &[S], alibi: Option<Alibi>, w: &mut [f64], order: &mut Vec<usize>, out: &mut [S]) {
+        let w = &mut w[..self.domain_len()];
+        self.scores_into(q, w);
+        self.weights_in_place(w, order, alibi);
+        self.mix_into(w, out);
+        if let Some(o) = &self.observer {
+            o.on_read(q, w, out);
+        }
+    }
 
-        if self.m_slots > 0 {
-            keys.extend(self.km.clone());
-            vals.extend(self.vm.clone());
+    pub(crate) fn record_read(&mut self, w: &[f64]) {
+        let n_window = self.w_len;
+        self.stats.reads += 1;
+        self.stats.window_mass_sum += w[..n_window].iter().sum::<f64>();
+        self.stats.memory_mass_sum += w[n_window..self.domain_len()].iter().sum::<f64>();
+        for i in 0..self.m_slots {
+            if !self.occupied[i] {
+                continue;
+            }
+            let wi = w[n_window + i];
+            self.attention[i] += wi;
+            if wi >= self.read_hit_threshold {
+                self.read_hits[i] += 1;
+                self.read_refresh.apply(&mut self.age[i]);
+            }
         }
+    }
+
+    /// Read half of `step` against the current window and memory, without mutating
+    /// anything. Unlike `step`, it does not count toward `read_hits` or `ReadRefresh`.
+    /// `q` is not validated; see `try_query`.
+    pub fn query(&self, q: &[S]) -> Vec<S> {
+        self.query_with(q, self.alibi)
+    }
 
-        let scale = (self.d as f64).sqrt();
-        let scores: Vec<f64> = keys.iter().map(|kk| Self::dot(&q, kk) / scale).collect();
-        let w = Self::softmax(&scores);
-        Self::weighted_sum(&w, &vals)
+    /// `query`, rejecting a wrong-length or non-finite `q`.
+    pub fn try_query(&self, q: &[S]) -> Result<Vec<S>, KVMemError> {
+        self.check_input("q", q)?;
+        Ok(self.query(q))
+    }
+
+    pub(crate) fn query_with(&self, q: &[S], alibi: Option<Alibi>) -> Vec<S> {
+        let mut w = vec![0.0; self.domain_len()];
+        let mut out = vec![S::default(); self.d];
+        self.attend_into(q, alibi, &mut w, &mut Vec::new(), &mut out);
+        out
+    }
+
+    /// `query` for each of `qs` against the same window and memory, in order.
+    pub fn query_batch(&self, qs: &[Vec<S>]) -> Vec<Vec<S>> {
+        let mut w = vec![0.0; self.domain_len()];
+        let mut order = Vec::new();
+        qs.iter()
+            .map(|q| {
+                let mut out = vec![S::default(); self.d];
+                self.attend_into(q, self.alibi, &mut w, &mut order, &mut out);
+                out
+            })
+            .collect()
+    }
+
+    /// `query_batch` with queries scored in parallel; results are identical and in order.
+    #[cfg(feature = "rayon")]
+    pub fn par_query_batch(&self, qs: &[Vec<S>]) -> Vec<Vec<S>> {
+        use rayon::prelude::*;
+        qs.par_iter().map(|q| self.query(q)).collect()
+    }
+
+    // Read without updating the read counters; the weights are left in `step_weights()`.
+    pub(crate) fn attend_scratch(&mut self, q: &[S], alibi: Option<Alibi>, out: &mut [S]) {
+        // Taking the Vecs out of self leaves empty, unallocated Vecs behind.
+        let mut w = std::mem::take(&mut self.scratch);
+        let mut order = std::mem::take(&mut self.slot_order);
+        self.attend_into(q, alibi, &mut w, &mut order, out);
+        self.scratch = w;
+        self.slot_order = order;
+    }
+
+    pub(crate) fn step_weights(&self) -> &[f64] {
+        &self.scratch[..self.domain_len()]
+    }
+
+    // Read that updates the per-slot read counters, as `step` does.
+    pub(crate) fn read_into(&mut self, q: &[S], alibi: Option<Alibi>, out: &mut [S]) {
+        self.attend_scratch(q, alibi, out);
+        let w = std::mem::take(&mut self.scratch);
+        self.record_read(&w);
+        self.scratch = w;
+    }
+
+    /// Allocation-free `step` (unless an LSH index is enabled): writes the
+    /// d-dimensional output into `out`.
+    pub fn step_into(&mut self, q: &[S], k: &[S], v: &[S], write_event: bool, out: &mut [S]) {
+        assert_eq!(out.len(), self.d, "step_into: out has length {}, expected {}", out.len(), self.d);
+        self.ingest(k, v, write_event);
+        self.read_into(q, self.alibi, out);
+    }
+
+    /// `ingest(k, v, write_event)` followed by a read of `q`; the output equals
+    /// `query(q)` after the ingest. The read also updates per-slot read statistics.
+    pub fn step(&mut self, q: Vec<S>, k: Vec<S>, v: Vec<S>, write_event: bool) -> Vec<S> {
+        let mut out = vec![S::default(); self.d];
+        self.step_into(&q, &k, &v, write_event, &mut out);
+        out
+    }
+
+    pub fn try_step(&mut self, q: Vec<S>, k: Vec<S>, v: Vec<S>, write_event: bool) -> Result<Vec<S>, KVMemError> {
+        self.check_input("q", &q)?;
+        self.check_input("k", &k)?;
+        self.check_input("v", &v)?;
+        Ok(self.step(q, k, v, write_event))
+    }
+
+    // Non-f64 memories hash their scalar type; stored values are hashed widened to f64.
+    fn hash_scalar(h: &mut Sha256) {
+        if S::TAG != f64::TAG {
+            h.update(b"scalar");
+            h.update([S::TAG]);
+        }
+    }
+
+    // One byte (0 or 1) per slot.
+    fn occupancy_bytes(&self) -> Vec<u8> {
+        self.occupied.iter().map(|&o| o as u8).collect()
     }
 
     pub fn state_sha256(&self) -> String {
         let mut h = Sha256::new();
 
-        h.update((self.l_window as u64).to_le_bytes());
-        h.update((self.m_slots as u64).to_le_bytes());
-        h.update((self.d as u64).to_le_bytes());
-        h.update(self.tau_reuse.to_le_bytes());
-        h.update(self.tau_novel.to_le_bytes());
-        h.update(self.g_write.to_le_bytes());
+        self.config().hash_into(&mut h);
+        Self::hash_scalar(&mut h);
 
-        for row in &self.kw {
-            for x in row {
-                h.update(x.to_le_bytes());
+        // Window rows in logical (oldest-to-newest) order, independent of the ring offset.
+        for i in 0..self.w_len {
+            for x in self.kw_row(i) {
+                h.update(x.to_f64().to_le_bytes());
             }
         }
-        for row in &self.vw {
-            for x in row {
-                h.update(x.to_le_bytes());
+        for i in 0..self.w_len {
+            for x in self.vw_row(i) {
+                h.update(x.to_f64().to_le_bytes());
             }
         }
-        for row in &self.km {
-            for x in row {
+        for x in &self.km {
+            h.update(x.to_f64().to_le_bytes());
+        }
+        for x in &self.vm {
+            h.update(x.to_f64().to_le_bytes());
+        }
+        for a in &self.age {
+            h.update(a.to_le_bytes());
+        }
+        h.update(self.occupancy_bytes());
+
+        if self.replacement != Replacement::Lru || self.policy.is_some() {
+            for a in &self.insert_age {
+                h.update(a.to_le_bytes());
+            }
+            for n in &self.read_hits {
+                h.update(n.to_le_bytes());
+            }
+            for x in &self.attention {
                 h.update(x.to_le_bytes());
             }
         }
-        for row in &self.vm {
-            for x in row {
-                h.update(x.to_le_bytes());
+
+        if self.write_rule == WriteRule::RunningMean {
+            for n in &self.write_count {
+                h.update(n.to_le_bytes());
             }
         }
-        for a in &self.age {
-            h.update(a.to_le_bytes());
+
+        // Slot positions only matter to reads that rotate slot keys.
+        if self.rope.is_some_and(|r| r.memory == MemoryPosition::WritePosition) {
+            h.update(b"position");
+            h.update(self.position.to_le_bytes());
+            for p in &self.write_pos {
+                h.update(p.to_le_bytes());
+            }
         }
 
         hex::encode(h.finalize())
@@ -232,8 +904,16 @@ impl KVMemV0 {
         h.update(self.tau_reuse.to_le_bytes());
         h.update(self.tau_novel.to_le_bytes());
         h.update(self.g_write.to_le_bytes());
-        for row in &self.km { for x in row { h.update(x.to_le_bytes()); } }
-        for row in &self.vm { for x in row { h.update(x.to_le_bytes()); } }
+        if let Some(g) = self.write_gains {
+            h.update(b"write_gains");
+            for (_, x) in g.named() {
+                h.update(x.to_le_bytes());
+            }
+        }
+        Self::hash_scalar(&mut h);
+        for x in &self.km { h.update(x.to_f64().to_le_bytes()); }
+        for x in &self.vm { h.update(x.to_f64().to_le_bytes()); }
+        h.update(self.occupancy_bytes());
         hex::encode(h.finalize())
     }
 }
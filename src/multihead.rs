@@ -0,0 +1,203 @@
+use sha2::{Digest, Sha256};
+
+use crate::{Alibi, KVMemConfig, KVMemError, KVMemV0, MemoryBias, WriteOutcome};
+
+/// Multi-head KV memory with optional grouped-query attention.
+///
+/// Keys and values carry `n_kv_heads` head slices of size `d_head`; each KV head owns a
+/// `KVMemV0` (window, memory slots, novelty gate, LRU ages) and is written once per step.
+/// Queries carry `n_heads` slices; query head `h` reads from KV head
+/// `h / (n_heads / n_kv_heads)`, and head outputs are concatenated. With
+/// `n_kv_heads == n_heads` this is plain multi-head attention. Query heads can
+/// use their own ALiBi slopes (`with_alibi_slopes`).
+///
+/// A `step` records one read per KV head, with the mean weights of its query
+/// heads, so `read_hits`, `ReadRefresh` and read stats do not scale with the group size.
+#[derive(Clone)]
+pub struct MultiHeadKVMem {
+    pub n_heads: usize,
+    pub n_kv_heads: usize,
+    pub d_head: usize,
+    heads: Vec<KVMemV0>,
+    // One per query head, or empty to use the head config's alibi.
+    alibi_slopes: Vec<f64>,
+    // Mean read weights of one query-head group, sized like a head's attention domain.
+    group_weights: Vec<f64>,
+}
+
+impl MultiHeadKVMem {
+    /// `head_cfg.d` is the per-head dimension.
+    pub fn new(n_heads: usize, head_cfg: &KVMemConfig) -> Result<Self, KVMemError> {
+        Self::grouped(n_heads, n_heads, head_cfg)
+    }
+
+    /// Grouped-query attention: `n_heads / n_kv_heads` query heads share each KV head.
+    pub fn grouped(n_heads: usize, n_kv_heads: usize, head_cfg: &KVMemConfig) -> Result<Self, KVMemError> {
+        if n_heads == 0 {
+            return Err(KVMemError::ZeroHeads);
+        }
+        if n_kv_heads == 0 || !n_heads.is_multiple_of(n_kv_heads) {
+            return Err(KVMemError::HeadGrouping { n_heads, n_kv_heads });
+        }
+        let head = KVMemV0::from_config(head_cfg)?;
+        Ok(Self {
+            n_heads,
+            n_kv_heads,
+            d_head: head_cfg.d,
+            heads: vec![head; n_kv_heads],
+            alibi_slopes: Vec::new(),
+            group_weights: vec![0.0; head_cfg.l_window + head_cfg.m_slots],
+        })
+    }
+
+    /// Gives query head `h` the ALiBi slope `slopes[h]` (e.g. `Alibi::geometric_slopes`).
+    /// The memory bias is taken from the head config's `alibi`, `Exempt` if unset.
+    pub fn with_alibi_slopes(mut self, slopes: &[f64]) -> Result<Self, KVMemError> {
+        if slopes.len() != self.n_heads {
+            return Err(KVMemError::DimensionMismatch { name: "alibi_slopes", expected: self.n_heads, got: slopes.len() });
+        }
+        if let Some(&value) = slopes.iter().find(|&&s| !(s > 0.0 && s.is_finite())) {
+            return Err(KVMemError::NotPositive { name: "alibi_slope", value });
+        }
+        self.alibi_slopes = slopes.to_vec();
+        Ok(self)
+    }
+
+    /// ALiBi applied to reads of query head `h`.
+    pub fn head_alibi(&self, h: usize) -> Option<Alibi> {
+        Self::alibi_for(&self.alibi_slopes, h, self.head(h))
+    }
+
+    fn alibi_for(slopes: &[f64], h: usize, head: &KVMemV0) -> Option<Alibi> {
+        match slopes.get(h) {
+            Some(&slope) => Some(Alibi { slope, memory: head.alibi.map_or(MemoryBias::Exempt, |a| a.memory) }),
+            None => head.alibi,
+        }
+    }
+
+    pub fn group_size(&self) -> usize {
+        self.n_heads / self.n_kv_heads
+    }
+
+    /// Length of q and of the output.
+    pub fn d_model(&self) -> usize {
+        self.n_heads * self.d_head
+    }
+
+    /// Length of k and v.
+    pub fn d_kv(&self) -> usize {
+        self.n_kv_heads * self.d_head
+    }
+
+    /// Memory read by query head `h`.
+    pub fn head(&self, h: usize) -> &KVMemV0 {
+        &self.heads[h / self.group_size()]
+    }
+
+    pub fn kv_head(&self, g: usize) -> &KVMemV0 {
+        &self.heads[g]
+    }
+
+    fn check_input(name: &'static str, x: &[f64], expected: usize) -> Result<(), KVMemError> {
+        if x.len() != expected {
+            return Err(KVMemError::DimensionMismatch { name, expected, got: x.len() });
+        }
+        match x.iter().position(|v| !v.is_finite()) {
+            Some(index) => Err(KVMemError::NonFiniteInput { name, index }),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes each KV head's slice of `k`/`v` (see `KVMemV0::ingest`); one outcome per KV head.
+    /// Panics if `k` or `v` is shorter than `d_kv()`; see `try_ingest`.
+    pub fn ingest(&mut self, k: &[f64], v: &[f64], write_event: bool) -> Vec<WriteOutcome> {
+        let dh = self.d_head;
+        let heads = self.heads.iter_mut().enumerate();
+        heads.map(|(g, head)| head.ingest(&k[g * dh..(g + 1) * dh], &v[g * dh..(g + 1) * dh], write_event)).collect()
+    }
+
+    pub fn try_ingest(&mut self, k: &[f64], v: &[f64], write_event: bool) -> Result<Vec<WriteOutcome>, KVMemError> {
+        Self::check_input("k", k, self.d_kv())?;
+        Self::check_input("v", v, self.d_kv())?;
+        Ok(self.ingest(k, v, write_event))
+    }
+
+    /// Side-effect-free read of every query head (see `KVMemV0::query`).
+    /// Panics if `q` is shorter than `d_model()`; see `try_query`.
+    pub fn query(&self, q: &[f64]) -> Vec<f64> {
+        let dh = self.d_head;
+        (0..self.n_heads).flat_map(|h| self.head(h).query_with(&q[h * dh..(h + 1) * dh], self.head_alibi(h))).collect()
+    }
+
+    pub fn try_query(&self, q: &[f64]) -> Result<Vec<f64>, KVMemError> {
+        Self::check_input("q", q, self.d_model())?;
+        Ok(self.query(q))
+    }
+
+    /// Allocation-free `step`: `out` has length `d_model()`.
+    pub fn step_into(&mut self, q: &[f64], k: &[f64], v: &[f64], write_event: bool, out: &mut [f64]) {
+        assert_eq!(out.len(), self.d_model(), "step_into: out has length {}, expected {}", out.len(), self.d_model());
+        let dh = self.d_head;
+        let group = self.group_size();
+        for (g, head) in self.heads.iter_mut().enumerate() {
+            let r = g * dh..(g + 1) * dh;
+            head.ingest(&k[r.clone()], &v[r], write_event);
+            let mean = &mut self.group_weights[..head.domain_len()];
+            mean.fill(0.0);
+            for h in g * group..(g + 1) * group {
+                let r = h * dh..(h + 1) * dh;
+                let alibi = Self::alibi_for(&self.alibi_slopes, h, head);
+                head.attend_scratch(&q[r.clone()], alibi, &mut out[r]);
+                for (m, &w) in mean.iter_mut().zip(head.step_weights()) {
+                    *m += w / group as f64;
+                }
+            }
+            head.record_read(mean);
+        }
+    }
+
+    pub fn step(&mut self, q: Vec<f64>, k: Vec<f64>, v: Vec<f64>, write_event: bool) -> Vec<f64> {
+        let mut out = vec![0.0; self.d_model()];
+        self.step_into(&q, &k, &v, write_event, &mut out);
+        out
+    }
+
+    pub fn try_step(&mut self, q: Vec<f64>, k: Vec<f64>, v: Vec<f64>, write_event: bool) -> Result<Vec<f64>, KVMemError> {
+        Self::check_input("q", &q, self.d_model())?;
+        Self::check_input("k", &k, self.d_kv())?;
+        Self::check_input("v", &v, self.d_kv())?;
+        Ok(self.step(q, k, v, write_event))
+    }
+
+    /// Memory hash of KV head `g`.
+    pub fn head_memory_kv_sha256(&self, g: usize) -> String {
+        self.heads[g].memory_kv_sha256()
+    }
+
+    fn combined_sha256(&self, per_head: impl Fn(&KVMemV0) -> String) -> String {
+        let mut h = Sha256::new();
+        h.update((self.n_heads as u64).to_le_bytes());
+        h.update((self.n_kv_heads as u64).to_le_bytes());
+        h.update((self.d_head as u64).to_le_bytes());
+        if !self.alibi_slopes.is_empty() {
+            h.update(b"alibi_slopes");
+            for s in &self.alibi_slopes {
+                h.update(s.to_le_bytes());
+            }
+        }
+        for head in &self.heads {
+            h.update(hex::decode(per_head(head)).expect("sha256 hex"));
+        }
+        hex::encode(h.finalize())
+    }
+
+    /// sha256 over (n_heads, n_kv_heads, d_head, any per-head ALiBi slopes,
+    /// per-KV-head state_sha256 digests in order).
+    pub fn state_sha256(&self) -> String {
+        self.combined_sha256(KVMemV0::state_sha256)
+    }
+
+    pub fn memory_kv_sha256(&self) -> String {
+        self.combined_sha256(KVMemV0::memory_kv_sha256)
+    }
+}
@@ -0,0 +1,32 @@
+use crate::Scalar;
+
+/// Callbacks for memory lifecycle events, registered with `KVMemV0::set_observer`.
+///
+/// Every method defaults to a no-op, so implementors override only what they
+/// need. Observers see state but cannot change it: they are not hashed,
+/// snapshotted or serialized, and clones of a memory share the observer.
+pub trait MemoryObserver<S: Scalar = f64>: Send + Sync {
+    /// Novel key/value written into `slot`.
+    fn on_write(&self, _slot: usize, _key: &[S], _value: &[S]) {}
+
+    /// Reuse (EMA) update of `slot`; `key`/`value` are the incoming token.
+    fn on_reuse(&self, _slot: usize, _similarity: f64, _key: &[S], _value: &[S]) {}
+
+    /// Novelty gate dropped a write that was too close to `slot`.
+    fn on_gate(&self, _slot: usize, _similarity: f64) {}
+
+    /// A novel write is about to overwrite occupied `slot`;
+    /// called with its old contents and age, before `on_write`.
+    fn on_evict(&self, _slot: usize, _key: &[S], _value: &[S], _age: u64) {}
+
+    /// Occupied `slot` passed `slot_ttl` and is about to be freed; called with
+    /// its old contents and age.
+    fn on_expire(&self, _slot: usize, _key: &[S], _value: &[S], _age: u64) {}
+
+    /// The oldest window token is about to be overwritten by a push.
+    fn on_window_evict(&self, _key: &[S], _value: &[S]) {}
+
+    /// A read (`step`, `query`, `query_batch`, traces). `weights` cover the window
+    /// oldest-to-newest, then the memory slots.
+    fn on_read(&self, _q: &[S], _weights: &[f64], _output: &[S]) {}
+}
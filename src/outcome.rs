@@ -0,0 +1,33 @@
+/// Which branch of the novelty-gated write path a token took.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WriteOutcome {
+    /// `m_slots == 0`: only the window is written.
+    NoMemory,
+    /// `write_event` was false.
+    NoWrite,
+    /// `similarity >= tau_reuse`: `write_rule` update of the most similar slot.
+    Reused { slot: usize, similarity: f64 },
+    /// `tau_novel <= similarity < tau_reuse`: dropped as a near-duplicate of `slot`.
+    Gated { slot: usize, similarity: f64 },
+    /// Novel key written into the victim `slot`. `evicted_age` is the age of the
+    /// entry it replaced, `None` for an unoccupied slot. `similarity` is the best
+    /// match among the previously occupied slots (-inf when there were none).
+    Written { slot: usize, similarity: f64, evicted_age: Option<u64> },
+}
+
+impl WriteOutcome {
+    /// True when memory slots changed (`Reused` or `Written`).
+    pub fn wrote_memory(&self) -> bool {
+        matches!(self, WriteOutcome::Reused { .. } | WriteOutcome::Written { .. })
+    }
+
+    /// Slot index the outcome refers to, if any.
+    pub fn slot(&self) -> Option<usize> {
+        match *self {
+            WriteOutcome::Reused { slot, .. } | WriteOutcome::Gated { slot, .. } | WriteOutcome::Written { slot, .. } => {
+                Some(slot)
+            }
+            WriteOutcome::NoMemory | WriteOutcome::NoWrite => None,
+        }
+    }
+}
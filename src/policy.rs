@@ -0,0 +1,251 @@
+use crate::scalar::Reals;
+
+// Slot key rows: a memory's stored rows (any scalar type) or caller-provided f64 rows.
+#[derive(Clone, Copy)]
+pub(crate) enum Keys<'a> {
+    Stored(&'a dyn Reals),
+    F64(&'a [f64]),
+}
+
+/// Per-slot bookkeeping a replacement policy may consult when a novel write
+/// needs a victim slot. All counters are indexed by slot.
+pub struct SlotMeta<'a> {
+    pub(crate) d: usize,
+    pub(crate) keys: Keys<'a>,
+    pub(crate) age: &'a [u64],
+    pub(crate) insert_age: &'a [u64],
+    pub(crate) read_hits: &'a [u64],
+    pub(crate) attention: &'a [f64],
+}
+
+impl<'a> SlotMeta<'a> {
+    /// Metadata over caller-provided slots, e.g. to unit-test a policy. `keys`
+    /// holds one row of `d` per slot, row-major.
+    ///
+    /// Panics if the counters differ in length or `keys` is not `d` per slot.
+    pub fn new(
+        d: usize,
+        keys: &'a [f64],
+        age: &'a [u64],
+        insert_age: &'a [u64],
+        read_hits: &'a [u64],
+        attention: &'a [f64],
+    ) -> Self {
+        let n = age.len();
+        assert!(
+            insert_age.len() == n && read_hits.len() == n && attention.len() == n && keys.len() == n * d,
+            "SlotMeta::new: counters and keys must cover the same {} slots",
+            n
+        );
+        SlotMeta { d, keys: Keys::F64(keys), age, insert_age, read_hits, attention }
+    }
+}
+
+impl SlotMeta<'_> {
+    pub fn len(&self) -> usize {
+        self.age.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.age.is_empty()
+    }
+
+    /// Key of slot `i`, widened to f64.
+    pub fn key(&self, i: usize) -> impl Iterator<Item = f64> + '_ {
+        (i * self.d..(i + 1) * self.d).map(move |j| match self.keys {
+            Keys::Stored(k) => k.real(j),
+            Keys::F64(k) => k[j],
+        })
+    }
+
+    /// Steps since the slot was last written (novel or reuse).
+    pub fn age(&self, i: usize) -> u64 {
+        self.age[i]
+    }
+
+    /// Steps since the slot last received a novel write.
+    pub fn insert_age(&self, i: usize) -> u64 {
+        self.insert_age[i]
+    }
+
+    /// Reads in `step` that gave the slot a dominant attention weight.
+    pub fn read_hits(&self, i: usize) -> u64 {
+        self.read_hits[i]
+    }
+
+    /// Cumulative softmax weight the slot received in `step` reads.
+    pub fn attention(&self, i: usize) -> f64 {
+        self.attention[i]
+    }
+}
+
+/// Victim choice for novel writes into a full memory: the built-in policies
+/// behind `Replacement`, or a custom one set with `KVMemV0::set_replacement_policy`.
+pub trait ReplacementPolicy {
+    /// Slot to overwrite on a novel write when every slot is occupied, in
+    /// `0..slots.len()`. Must be deterministic; the built-in policies resolve
+    /// remaining ties to the lowest index.
+    fn victim(&self, slots: &SlotMeta<'_>) -> usize;
+}
+
+// Ties -> lowest index, matching the manifest tie_break rule.
+fn argmax_by<T: PartialOrd>(n: usize, f: impl Fn(usize) -> T) -> usize {
+    let mut j = 0usize;
+    for i in 1..n {
+        if f(i) > f(j) {
+            j = i;
+        }
+    }
+    j
+}
+
+// Ties -> oldest by age, then lowest index, so equal scores (e.g. several
+// never-read slots) degrade to LRU instead of always hitting slot 0.
+fn argmin_then_lru<T: PartialOrd>(s: &SlotMeta<'_>, f: impl Fn(usize) -> T) -> usize {
+    let mut j = 0usize;
+    for i in 1..s.len() {
+        let (fi, fj) = (f(i), f(j));
+        if fi < fj || (fi == fj && s.age(i) > s.age(j)) {
+            j = i;
+        }
+    }
+    j
+}
+
+/// Oldest by `age` (the v0 behaviour).
+pub struct Lru;
+
+/// Fewest read hits.
+pub struct Lfu;
+
+/// Least cumulative attention weight.
+pub struct LeastAttended;
+
+/// Smallest key norm.
+pub struct LowestNorm;
+
+/// Oldest novel write; reuse updates do not refresh the slot.
+pub struct Fifo;
+
+impl ReplacementPolicy for Lru {
+    fn victim(&self, s: &SlotMeta<'_>) -> usize {
+        argmax_by(s.len(), |i| s.age(i))
+    }
+}
+
+impl ReplacementPolicy for Lfu {
+    fn victim(&self, s: &SlotMeta<'_>) -> usize {
+        argmin_then_lru(s, |i| s.read_hits(i))
+    }
+}
+
+impl ReplacementPolicy for LeastAttended {
+    fn victim(&self, s: &SlotMeta<'_>) -> usize {
+        argmin_then_lru(s, |i| s.attention(i))
+    }
+}
+
+impl ReplacementPolicy for LowestNorm {
+    fn victim(&self, s: &SlotMeta<'_>) -> usize {
+        argmin_then_lru(s, |i| s.key(i).map(|x| x * x).sum::<f64>())
+    }
+}
+
+impl ReplacementPolicy for Fifo {
+    fn victim(&self, s: &SlotMeta<'_>) -> usize {
+        argmax_by(s.len(), |i| s.insert_age(i))
+    }
+}
+
+/// Built-in replacement policies, selectable through `KVMemConfig::replacement`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Replacement {
+    #[default]
+    Lru,
+    Lfu,
+    LeastAttended,
+    LowestNorm,
+    Fifo,
+}
+
+impl Replacement {
+    pub const ALL: [Replacement; 5] =
+        [Replacement::Lru, Replacement::Lfu, Replacement::LeastAttended, Replacement::LowestNorm, Replacement::Fifo];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Replacement::Lru => "lru",
+            Replacement::Lfu => "lfu",
+            Replacement::LeastAttended => "least_attended",
+            Replacement::LowestNorm => "lowest_norm",
+            Replacement::Fifo => "fifo",
+        }
+    }
+
+    pub(crate) fn tag(self) -> u8 {
+        self as u8
+    }
+
+    pub(crate) fn from_tag(tag: u8) -> Option<Self> {
+        Self::ALL.get(tag as usize).copied()
+    }
+
+    pub fn policy(self) -> &'static dyn ReplacementPolicy {
+        match self {
+            Replacement::Lru => &Lru,
+            Replacement::Lfu => &Lfu,
+            Replacement::LeastAttended => &LeastAttended,
+            Replacement::LowestNorm => &LowestNorm,
+            Replacement::Fifo => &Fifo,
+        }
+    }
+}
+
+impl ReplacementPolicy for Replacement {
+    fn victim(&self, slots: &SlotMeta<'_>) -> usize {
+        self.policy().victim(slots)
+    }
+}
+
+/// What a read hit in `step` does to the slot's LRU age.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ReadRefresh {
+    /// Ages only reset on writes (v0).
+    #[default]
+    Off,
+    /// A read hit resets the age to 0, as a write would.
+    Reset,
+    /// A read hit subtracts this many steps from the age (saturating at 0).
+    Decrement(u64),
+}
+
+impl ReadRefresh {
+    pub(crate) fn tag(self) -> (u8, u64) {
+        match self {
+            ReadRefresh::Off => (0, 0),
+            ReadRefresh::Reset => (1, 0),
+            ReadRefresh::Decrement(n) => (2, n),
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8, n: u64) -> Option<Self> {
+        match tag {
+            0 => Some(ReadRefresh::Off),
+            1 => Some(ReadRefresh::Reset),
+            2 => Some(ReadRefresh::Decrement(n)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn apply(self, age: &mut u64) {
+        match self {
+            ReadRefresh::Off => {}
+            ReadRefresh::Reset => *age = 0,
+            ReadRefresh::Decrement(n) => *age = age.saturating_sub(n),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::Scalar;
+
+/// Rotary position embedding, applied to `q` and window keys at read time.
+///
+/// Coordinate pairs `(2i, 2i + 1)` of a vector at position `p` are rotated by
+/// `p * base^(-2i / d)`; an odd last coordinate is left as is. Keys are stored
+/// unrotated, so the write path's cosine similarity is position-free. The query
+/// of a read sits at the position of the newest window token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rope {
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub base: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub memory: MemoryPosition,
+}
+
+/// Position of memory-slot keys under `Rope`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MemoryPosition {
+    /// Slot keys are not rotated: memory reads ignore position.
+    #[default]
+    Free,
+    /// Slot keys are rotated to the position of their last write (novel or reuse).
+    WritePosition,
+}
+
+impl Rope {
+    pub fn new(base: f64) -> Self {
+        Rope { base, memory: MemoryPosition::Free }
+    }
+
+    // Snapshot encoding of an optional rope: 0 none, 1 free, 2 write_position.
+    pub(crate) fn tag(rope: Option<Rope>) -> (u8, f64) {
+        match rope {
+            None => (0, 0.0),
+            Some(Rope { base, memory: MemoryPosition::Free }) => (1, base),
+            Some(Rope { base, memory: MemoryPosition::WritePosition }) => (2, base),
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8, base: f64) -> Option<Option<Rope>> {
+        match tag {
+            0 => Some(None),
+            1 => Some(Some(Rope { base, memory: MemoryPosition::Free })),
+            2 => Some(Some(Rope { base, memory: MemoryPosition::WritePosition })),
+            _ => None,
+        }
+    }
+
+    // Bit-level identity, for config equality and hashing.
+    pub(crate) fn bits(rope: Option<Rope>) -> (u8, u64) {
+        let (tag, base) = Self::tag(rope);
+        (tag, base.to_bits())
+    }
+
+    // Score of a query at position t against a key at position t - distance:
+    // R(t) q . R(t - distance) k = q . R(-distance) k.
+    pub(crate) fn score<S: Scalar>(&self, q: &[S], k: &[S], distance: f64) -> f64 {
+        let d = q.len();
+        let mut s = 0.0;
+        for i in 0..d / 2 {
+            let theta = self.base.powf(-2.0 * i as f64 / d as f64);
+            let (sin, cos) = (distance * theta).sin_cos();
+            let (q0, q1) = (q[2 * i].to_f64(), q[2 * i + 1].to_f64());
+            let (k0, k1) = (k[2 * i].to_f64(), k[2 * i + 1].to_f64());
+            s += q0 * (k0 * cos + k1 * sin) + q1 * (k1 * cos - k0 * sin);
+        }
+        if d % 2 == 1 {
+            s += q[d - 1].to_f64() * k[d - 1].to_f64();
+        }
+        s
+    }
+}
+
+/// ALiBi-style linear recency penalty on read logits.
+///
+/// After scaling, a window key `j` tokens older than the newest loses
+/// `slope * j`; memory slots lose `slope * age` under `MemoryBias::Age` and
+/// nothing under `MemoryBias::Exempt`. `memory_top_k` ranks slots after the penalty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alibi {
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub slope: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub memory: MemoryBias,
+}
+
+/// Penalty on memory-slot logits under `Alibi`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MemoryBias {
+    /// Slots are not penalized.
+    #[default]
+    Exempt,
+    /// Slots are penalized by their age (`KVMemV0::slot_ages`).
+    Age,
+}
+
+impl Alibi {
+    pub fn new(slope: f64) -> Self {
+        Alibi { slope, memory: MemoryBias::Exempt }
+    }
+
+    /// The standard ALiBi slopes for `n_heads` heads: `2^(-8h / n_heads)` for
+    /// h = 1..=n_heads, for `MultiHeadKVMem::with_alibi_slopes`.
+    pub fn geometric_slopes(n_heads: usize) -> Vec<f64> {
+        (1..=n_heads).map(|h| 2f64.powf(-8.0 * h as f64 / n_heads as f64)).collect()
+    }
+
+    // Snapshot encoding of an optional alibi: 0 none, 1 exempt, 2 age.
+    pub(crate) fn tag(alibi: Option<Alibi>) -> (u8, f64) {
+        match alibi {
+            None => (0, 0.0),
+            Some(Alibi { slope, memory: MemoryBias::Exempt }) => (1, slope),
+            Some(Alibi { slope, memory: MemoryBias::Age }) => (2, slope),
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8, slope: f64) -> Option<Option<Alibi>> {
+        match tag {
+            0 => Some(None),
+            1 => Some(Some(Alibi { slope, memory: MemoryBias::Exempt })),
+            2 => Some(Some(Alibi { slope, memory: MemoryBias::Age })),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn bits(alibi: Option<Alibi>) -> (u8, u64) {
+        let (tag, slope) = Self::tag(alibi);
+        (tag, slope.to_bits())
+    }
+
+    // Subtracts the penalty from scaled logits split at the window/memory boundary.
+    pub(crate) fn apply(&self, window: &mut [f64], memory: &mut [f64], age: &[u64]) {
+        let n = window.len();
+        for (i, x) in window.iter_mut().enumerate() {
+            *x -= self.slope * (n - 1 - i) as f64;
+        }
+        if self.memory == MemoryBias::Age {
+            for (x, &a) in memory.iter_mut().zip(age) {
+                *x -= self.slope * a as f64;
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+/// How a read combines window and memory attention.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ReadMode {
+    /// One softmax over window and memory keys together (v0).
+    #[default]
+    Joint,
+    /// Separate softmaxes over window and memory, mixed as
+    /// `memory_weight * memory + (1 - memory_weight) * window`.
+    FixedGate {
+        #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+        memory_weight: f64,
+    },
+    /// Separate softmaxes, mixed with the query-dependent memory weight
+    /// `sigmoid((max memory logit - max window logit) / temperature)`.
+    ScoreGate {
+        #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+        temperature: f64,
+    },
+}
+
+impl ReadMode {
+    pub(crate) fn tag(self) -> (u8, f64) {
+        match self {
+            ReadMode::Joint => (0, 0.0),
+            ReadMode::FixedGate { memory_weight } => (1, memory_weight),
+            ReadMode::ScoreGate { temperature } => (2, temperature),
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8, x: f64) -> Option<Self> {
+        match tag {
+            0 => Some(ReadMode::Joint),
+            1 => Some(ReadMode::FixedGate { memory_weight: x }),
+            2 => Some(ReadMode::ScoreGate { temperature: x }),
+            _ => None,
+        }
+    }
+
+    // Bit-level identity, for config equality and hashing.
+    pub(crate) fn bits(self) -> (u8, u64) {
+        let (tag, x) = self.tag();
+        (tag, x.to_bits())
+    }
+
+    // Weight on the memory softmax, given both non-empty sets of scaled logits.
+    pub(crate) fn memory_weight(self, window: &[f64], memory: &[f64]) -> f64 {
+        match self {
+            ReadMode::Joint => unreachable!("joint reads are not gated"),
+            ReadMode::FixedGate { memory_weight } => memory_weight,
+            ReadMode::ScoreGate { temperature } => {
+                let max = |xs: &[f64]| xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                1.0 / (1.0 + (-(max(memory) - max(window)) / temperature).exp())
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Serde field adapters: f64 values serialize as round-trippable strings
+//! (`{:?}` formatting, parsed back bit-exactly) so that every output format is
+//! exact and canonical JSON never depends on a float printer. Deserialization
+//! also accepts plain numbers, so hand-written configs can use `0.85`.
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+
+struct ReprF64(f64);
+
+impl Serialize for ReprF64 {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&format!("{:?}", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for ReprF64 {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = ReprF64;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "an f64 or a string holding one")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<ReprF64, E> {
+                s.parse::<f64>().map(ReprF64).map_err(|_| E::custom(format!("invalid f64 {:?}", s)))
+            }
+
+            fn visit_f64<E: de::Error>(self, x: f64) -> Result<ReprF64, E> {
+                Ok(ReprF64(x))
+            }
+
+            fn visit_i64<E: de::Error>(self, x: i64) -> Result<ReprF64, E> {
+                Ok(ReprF64(x as f64))
+            }
+
+            fn visit_u64<E: de::Error>(self, x: u64) -> Result<ReprF64, E> {
+                Ok(ReprF64(x as f64))
+            }
+        }
+
+        d.deserialize_any(V)
+    }
+}
+
+pub(crate) mod f64_str {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(x: &f64, s: S) -> Result<S::Ok, S::Error> {
+        ReprF64(*x).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<f64, D::Error> {
+        ReprF64::deserialize(d).map(|r| r.0)
+    }
+}
+
+pub(crate) mod rows_str {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(rows: &[Vec<f64>], s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(Some(rows.len()))?;
+        for row in rows {
+            let row: Vec<ReprF64> = row.iter().map(|&x| ReprF64(x)).collect();
+            seq.serialize_element(&row)?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vec<f64>>, D::Error> {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = Vec<Vec<f64>>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a list of f64 rows")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut a: A) -> Result<Self::Value, A::Error> {
+                let mut out = Vec::new();
+                while let Some(row) = a.next_element::<Vec<ReprF64>>()? {
+                    out.push(row.into_iter().map(|r| r.0).collect());
+                }
+                Ok(out)
+            }
+        }
+
+        d.deserialize_seq(V)
+    }
+}
+
+pub(crate) mod vec_str {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(xs: &[f64], s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(Some(xs.len()))?;
+        for &x in xs {
+            seq.serialize_element(&ReprF64(x))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<f64>, D::Error> {
+        Vec::<ReprF64>::deserialize(d).map(|xs| xs.into_iter().map(|r| r.0).collect())
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+/// Element type of a `KVMemV0`'s inputs, outputs and stored window/slot rows.
+///
+/// Similarities, softmax and slot updates are computed in f64; results are rounded
+/// to `Self` when stored or returned. With `S = f64` every conversion is the identity.
+///
+/// Sealed: only the types below implement it, so each `TAG` names one type in the hashes.
+pub trait Scalar: sealed::Sealed + Copy + Default + PartialEq + fmt::Debug + Send + Sync + 'static {
+    /// Short type name, e.g. `"f32"`.
+    const NAME: &'static str;
+    /// Stable id used in `state_sha256` / `memory_kv_sha256`.
+    const TAG: u8;
+
+    fn from_f64(x: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for f64 {}
+    impl Sealed for f32 {}
+    #[cfg(feature = "half")]
+    impl Sealed for half::bf16 {}
+}
+
+impl Scalar for f64 {
+    const NAME: &'static str = "f64";
+    const TAG: u8 = 0;
+
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl Scalar for f32 {
+    const NAME: &'static str = "f32";
+    const TAG: u8 = 1;
+
+    fn from_f64(x: f64) -> Self {
+        x as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+#[cfg(feature = "half")]
+impl Scalar for half::bf16 {
+    const NAME: &'static str = "bf16";
+    const TAG: u8 = 2;
+
+    fn from_f64(x: f64) -> Self {
+        half::bf16::from_f64(x)
+    }
+
+    fn to_f64(self) -> f64 {
+        half::bf16::to_f64(self)
+    }
+}
+
+// Object-safe f64 view of stored rows, so non-generic code such as the
+// replacement policies can read keys of any scalar type.
+pub(crate) trait Reals {
+    fn real(&self, i: usize) -> f64;
+}
+
+impl<S: Scalar> Reals for Vec<S> {
+    fn real(&self, i: usize) -> f64 {
+        self[i].to_f64()
+    }
+}
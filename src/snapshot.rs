@@ -0,0 +1,332 @@
+//! Versioned binary snapshot of a `KVMemV0`.
+//!
+//! All integers are u64 and all reals are f64, little-endian, except where noted:
+//!
+//! ```text
+//! magic        8 bytes   "KVMEMVSC"
+//! version      u32       SNAPSHOT_VERSION
+//! spec_len     u32       byte length of spec id
+//! spec_id      spec_len  "kv-memory/v0" (utf-8)
+//! config       l_window, m_slots, d, tau_reuse, tau_novel, g_write,
+//!              replacement (u8: 0 lru, 1 lfu, 2 least_attended, 3 lowest_norm, 4 fifo),
+//!              read_hit_threshold, read_refresh (u8: 0 off, 1 reset, 2 decrement; then u64 amount),
+//!              read_mode (u8: 0 joint, 1 fixed_gate, 2 score_gate; then real parameter),
+//!              memory_top_k (0 = every slot),
+//!              rope (u8: 0 none, 1 free memory, 2 write-position memory; then real base),
+//!              alibi (u8: 0 none, 1 exempt memory, 2 age-biased memory; then real slope),
+//!              write_rule (u8: 0 ema, 1 overwrite, 2 delta, 3 running_mean, 4 max_norm),
+//!              write_gains (u8: 0 none, 1 set; then reals key_reuse, value_reuse,
+//!              key_novel, value_novel, all 0 when none),
+//!              key_decay, value_decay, slot_ttl (0 = none)
+//! window_len   u64       number of window tokens (<= l_window)
+//! kw, vw       window_len * d reals each, oldest to newest
+//! km, vm       m_slots * d reals each
+//! age          m_slots u64
+//! insert_age   m_slots u64
+//! read_hits    m_slots u64
+//! attention    m_slots reals
+//! position     tokens ingested
+//! write_pos    m_slots u64
+//! write_count  m_slots u64
+//! occupied     m_slots u8 (0 empty, 1 occupied)
+//! checksum     32 bytes  sha256 of every preceding byte
+//! ```
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use sha2::{Digest, Sha256};
+
+use crate::state::check_write_positions;
+use crate::{Alibi, KVMemConfig, KVMemError, KVMemV0, ReadMode, ReadRefresh, Replacement, Rope, WriteGains, WriteRule, SPEC_ID};
+
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"KVMEMVSC";
+/// Format of the first release. A layout change after a release bumps this and
+/// keeps a reader for every released version.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u32),
+    SpecMismatch(String),
+    Truncated,
+    ChecksumMismatch,
+    Corrupt(&'static str),
+    Config(KVMemError),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot i/o error: {}", e),
+            SnapshotError::BadMagic => write!(f, "not a kv-memory snapshot (bad magic)"),
+            SnapshotError::UnsupportedVersion(v) => write!(f, "unsupported snapshot version {}", v),
+            SnapshotError::SpecMismatch(s) => write!(f, "snapshot spec id {:?} != {:?}", s, SPEC_ID),
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::ChecksumMismatch => write!(f, "snapshot checksum mismatch"),
+            SnapshotError::Corrupt(what) => write!(f, "corrupt snapshot: {}", what),
+            SnapshotError::Config(e) => write!(f, "invalid snapshot config: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(e) => Some(e),
+            SnapshotError::Config(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.pos.checked_add(n).ok_or(SnapshotError::Truncated)?;
+        if end > self.buf.len() {
+            return Err(SnapshotError::Truncated);
+        }
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn usize(&mut self) -> Result<usize, SnapshotError> {
+        usize::try_from(self.u64()?).map_err(|_| SnapshotError::Corrupt("length overflows usize"))
+    }
+
+    fn f64(&mut self) -> Result<f64, SnapshotError> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn fill(&mut self, out: &mut [f64]) -> Result<(), SnapshotError> {
+        for x in out {
+            *x = self.f64()?;
+        }
+        Ok(())
+    }
+}
+
+// Bytes after window_len: window and slot rows, the per-slot counters,
+// position, and one occupancy byte per slot. None on overflow.
+fn body_len(window_len: usize, m_slots: usize, d: usize) -> Option<usize> {
+    let window = window_len.checked_mul(d)?.checked_mul(2 * 8)?;
+    let slots = m_slots.checked_mul(d)?.checked_mul(2 * 8)?;
+    // age, insert_age, read_hits, attention, write_pos, write_count; then occupied.
+    let per_slot = m_slots.checked_mul(6 * 8 + 1)?;
+    window.checked_add(slots)?.checked_add(per_slot)?.checked_add(8)
+}
+
+fn put_reals<'a>(buf: &mut Vec<u8>, xs: impl IntoIterator<Item = &'a f64>) {
+    for x in xs {
+        buf.extend_from_slice(&x.to_le_bytes());
+    }
+}
+
+impl KVMemV0 {
+    pub fn to_snapshot_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&SNAPSHOT_MAGIC);
+        buf.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        buf.extend_from_slice(&(SPEC_ID.len() as u32).to_le_bytes());
+        buf.extend_from_slice(SPEC_ID.as_bytes());
+
+        buf.extend_from_slice(&(self.l_window as u64).to_le_bytes());
+        buf.extend_from_slice(&(self.m_slots as u64).to_le_bytes());
+        buf.extend_from_slice(&(self.d as u64).to_le_bytes());
+        buf.extend_from_slice(&self.tau_reuse.to_le_bytes());
+        buf.extend_from_slice(&self.tau_novel.to_le_bytes());
+        buf.extend_from_slice(&self.g_write.to_le_bytes());
+        buf.push(self.replacement.tag());
+        buf.extend_from_slice(&self.read_hit_threshold.to_le_bytes());
+        let (refresh, amount) = self.read_refresh.tag();
+        buf.push(refresh);
+        buf.extend_from_slice(&amount.to_le_bytes());
+        let (mode, x) = self.read_mode.tag();
+        buf.push(mode);
+        buf.extend_from_slice(&x.to_le_bytes());
+        buf.extend_from_slice(&(self.memory_top_k.unwrap_or(0) as u64).to_le_bytes());
+        let (rope, base) = Rope::tag(self.rope);
+        buf.push(rope);
+        buf.extend_from_slice(&base.to_le_bytes());
+        let (alibi, slope) = Alibi::tag(self.alibi);
+        buf.push(alibi);
+        buf.extend_from_slice(&slope.to_le_bytes());
+        buf.push(self.write_rule.tag());
+        buf.push(self.write_gains.is_some() as u8);
+        let gains = self.write_gains.map_or([0.0; 4], |g| g.named().map(|(_, x)| x));
+        put_reals(&mut buf, &gains);
+        put_reals(&mut buf, &[self.key_decay, self.value_decay]);
+        buf.extend_from_slice(&self.slot_ttl.unwrap_or(0).to_le_bytes());
+
+        buf.extend_from_slice(&(self.window_len() as u64).to_le_bytes());
+        put_reals(&mut buf, (0..self.window_len()).flat_map(|i| self.kw_row(i)));
+        put_reals(&mut buf, (0..self.window_len()).flat_map(|i| self.vw_row(i)));
+        put_reals(&mut buf, &self.km);
+        put_reals(&mut buf, &self.vm);
+        for a in self.age.iter().chain(&self.insert_age).chain(&self.read_hits) {
+            buf.extend_from_slice(&a.to_le_bytes());
+        }
+        for x in &self.attention {
+            buf.extend_from_slice(&x.to_le_bytes());
+        }
+        buf.extend_from_slice(&self.position.to_le_bytes());
+        for n in self.write_pos.iter().chain(&self.write_count) {
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+        buf.extend(self.occupied.iter().map(|&o| o as u8));
+
+        let digest = Sha256::digest(&buf);
+        buf.extend_from_slice(&digest);
+        buf
+    }
+
+    pub fn from_snapshot_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        if bytes.len() < SNAPSHOT_MAGIC.len() || bytes[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let mut c = Cursor { buf: bytes, pos: SNAPSHOT_MAGIC.len() };
+
+        let version = c.u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        // Verify before parsing so any flipped byte reports as a checksum failure.
+        let body_end = bytes.len().checked_sub(32).filter(|&n| n >= c.pos).ok_or(SnapshotError::Truncated)?;
+        if Sha256::digest(&bytes[..body_end]).as_slice() != &bytes[body_end..] {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+        c.buf = &bytes[..body_end];
+
+        let spec_len = c.u32()? as usize;
+        let spec = c.take(spec_len)?;
+        if spec != SPEC_ID.as_bytes() {
+            return Err(SnapshotError::SpecMismatch(String::from_utf8_lossy(spec).into_owned()));
+        }
+
+        let cfg = KVMemConfig {
+            l_window: c.usize()?,
+            m_slots: c.usize()?,
+            d: c.usize()?,
+            tau_reuse: c.f64()?,
+            tau_novel: c.f64()?,
+            g_write: c.f64()?,
+            replacement: Replacement::from_tag(c.u8()?).ok_or(SnapshotError::Corrupt("unknown replacement policy"))?,
+            read_hit_threshold: c.f64()?,
+            read_refresh: {
+                let (tag, amount) = (c.u8()?, c.u64()?);
+                ReadRefresh::from_tag(tag, amount).ok_or(SnapshotError::Corrupt("unknown read refresh mode"))?
+            },
+            read_mode: {
+                let (tag, x) = (c.u8()?, c.f64()?);
+                ReadMode::from_tag(tag, x).ok_or(SnapshotError::Corrupt("unknown read mode"))?
+            },
+            memory_top_k: Some(c.usize()?).filter(|&k| k > 0),
+            rope: {
+                let (tag, base) = (c.u8()?, c.f64()?);
+                Rope::from_tag(tag, base).ok_or(SnapshotError::Corrupt("unknown rope mode"))?
+            },
+            alibi: {
+                let (tag, slope) = (c.u8()?, c.f64()?);
+                Alibi::from_tag(tag, slope).ok_or(SnapshotError::Corrupt("unknown alibi mode"))?
+            },
+            write_rule: WriteRule::from_tag(c.u8()?).ok_or(SnapshotError::Corrupt("unknown write rule"))?,
+            write_gains: {
+                let set = c.u8()?;
+                let mut g = [0.0; 4];
+                c.fill(&mut g)?;
+                match set {
+                    0 => None,
+                    1 => Some(WriteGains { key_reuse: g[0], value_reuse: g[1], key_novel: g[2], value_novel: g[3] }),
+                    _ => return Err(SnapshotError::Corrupt("unknown write gains flag")),
+                }
+            },
+            key_decay: c.f64()?,
+            value_decay: c.f64()?,
+            slot_ttl: Some(c.u64()?).filter(|&t| t > 0),
+        };
+        let window_len = c.usize()?;
+        if window_len > cfg.l_window {
+            return Err(SnapshotError::Corrupt("window_len exceeds l_window"));
+        }
+        // The checksum only proves the bytes are intact: check the sizes the header
+        // claims against the bytes actually present before allocating for them.
+        if body_len(window_len, cfg.m_slots, cfg.d) != Some(body_end - c.pos) {
+            return Err(SnapshotError::Corrupt("body length does not match header"));
+        }
+        let mut m = KVMemV0::from_config(&cfg).map_err(SnapshotError::Config)?;
+
+        // The restored window starts at physical row 0; hashes and reads only see logical order.
+        let n = window_len * cfg.d;
+        c.fill(&mut m.kw[..n])?;
+        c.fill(&mut m.vw[..n])?;
+        m.w_len = window_len;
+        c.fill(&mut m.km)?;
+        c.fill(&mut m.vm)?;
+        for counters in [&mut m.age, &mut m.insert_age, &mut m.read_hits] {
+            for a in counters.iter_mut() {
+                *a = c.u64()?;
+            }
+        }
+        for x in m.attention.iter_mut() {
+            *x = c.f64()?;
+        }
+        m.position = c.u64()?;
+        for n in m.write_pos.iter_mut().chain(m.write_count.iter_mut()) {
+            *n = c.u64()?;
+        }
+        for o in m.occupied.iter_mut() {
+            *o = match c.u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(SnapshotError::Corrupt("occupancy byte is not 0 or 1")),
+            };
+        }
+        if check_write_positions(&m.write_pos, &m.occupied, m.position).is_err() {
+            return Err(SnapshotError::Corrupt("slot write position out of range"));
+        }
+
+        if c.pos != body_end {
+            return Err(SnapshotError::Corrupt("body length does not match header"));
+        }
+        Ok(m)
+    }
+
+    pub fn save<W: Write>(&self, w: &mut W) -> Result<(), SnapshotError> {
+        w.write_all(&self.to_snapshot_bytes())?;
+        Ok(())
+    }
+
+    /// Reads to end of stream; the stream must hold exactly one snapshot.
+    pub fn load<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)?;
+        Self::from_snapshot_bytes(&bytes)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{KVMemConfig, KVMemError, KVMemV0, SPEC_ID};
+
+/// Plain-data view of a `KVMemV0`: config plus window, memory slots and per-slot counters.
+///
+/// With the `serde` feature this is the serialized form of a memory; see
+/// `to_canonical_json` for the byte-stable JSON encoding.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KVMemState {
+    pub spec_id: String,
+    pub config: KVMemConfig,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::rows_str"))]
+    pub kw: Vec<Vec<f64>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::rows_str"))]
+    pub vw: Vec<Vec<f64>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::rows_str"))]
+    pub km: Vec<Vec<f64>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::rows_str"))]
+    pub vm: Vec<Vec<f64>>,
+    pub age: Vec<u64>,
+    pub insert_age: Vec<u64>,
+    pub read_hits: Vec<u64>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::vec_str"))]
+    pub attention: Vec<f64>,
+    pub position: u64,
+    pub write_pos: Vec<u64>,
+    pub write_count: Vec<u64>,
+    pub occupied: Vec<bool>,
+}
+
+fn check_rows(name: &'static str, rows: &[Vec<f64>], n: usize, d: usize) -> Result<(), KVMemError> {
+    if rows.len() != n {
+        return Err(KVMemError::DimensionMismatch { name, expected: n, got: rows.len() });
+    }
+    for row in rows {
+        if row.len() != d {
+            return Err(KVMemError::DimensionMismatch { name, expected: d, got: row.len() });
+        }
+    }
+    Ok(())
+}
+
+// An occupied slot was written before the current position; a free slot has none.
+pub(crate) fn check_write_positions(write_pos: &[u64], occupied: &[bool], position: u64) -> Result<(), KVMemError> {
+    for (slot, (&p, &o)) in write_pos.iter().zip(occupied).enumerate() {
+        let ahead = if o { p >= position } else { p != 0 };
+        if ahead {
+            return Err(KVMemError::WritePosition { slot, write_pos: p, position });
+        }
+    }
+    Ok(())
+}
+
+impl KVMemV0 {
+    pub fn to_state(&self) -> KVMemState {
+        KVMemState {
+            spec_id: SPEC_ID.to_string(),
+            config: self.config(),
+            kw: (0..self.window_len()).map(|i| self.kw_row(i).to_vec()).collect(),
+            vw: (0..self.window_len()).map(|i| self.vw_row(i).to_vec()).collect(),
+            km: self.km.chunks(self.d).map(<[f64]>::to_vec).collect(),
+            vm: self.vm.chunks(self.d).map(<[f64]>::to_vec).collect(),
+            age: self.age.clone(),
+            insert_age: self.insert_age.clone(),
+            read_hits: self.read_hits.clone(),
+            attention: self.attention.clone(),
+            position: self.position,
+            write_pos: self.write_pos.clone(),
+            write_count: self.write_count.clone(),
+            occupied: self.occupied.clone(),
+        }
+    }
+
+    pub fn from_state(state: KVMemState) -> Result<Self, KVMemError> {
+        if state.spec_id != SPEC_ID {
+            return Err(KVMemError::SpecMismatch(state.spec_id));
+        }
+        // Shapes are checked against the config before it sizes any allocation.
+        let cfg = &state.config;
+        if state.kw.len() > cfg.l_window {
+            return Err(KVMemError::DimensionMismatch { name: "kw", expected: cfg.l_window, got: state.kw.len() });
+        }
+        check_rows("kw", &state.kw, state.kw.len(), cfg.d)?;
+        check_rows("vw", &state.vw, state.kw.len(), cfg.d)?;
+        check_rows("km", &state.km, cfg.m_slots, cfg.d)?;
+        check_rows("vm", &state.vm, cfg.m_slots, cfg.d)?;
+        for (name, len) in [
+            ("age", state.age.len()),
+            ("insert_age", state.insert_age.len()),
+            ("read_hits", state.read_hits.len()),
+            ("attention", state.attention.len()),
+            ("write_pos", state.write_pos.len()),
+            ("write_count", state.write_count.len()),
+            ("occupied", state.occupied.len()),
+        ] {
+            if len != cfg.m_slots {
+                return Err(KVMemError::DimensionMismatch { name, expected: cfg.m_slots, got: len });
+            }
+        }
+        check_write_positions(&state.write_pos, &state.occupied, state.position)?;
+        let mut m = KVMemV0::from_config(cfg)?;
+
+        for (k, v) in state.kw.iter().zip(&state.vw) {
+            m.push_window(k, v);
+        }
+        m.km = state.km.concat();
+        m.vm = state.vm.concat();
+        m.age = state.age;
+        m.insert_age = state.insert_age;
+        m.read_hits = state.read_hits;
+        m.attention = state.attention;
+        m.position = state.position;
+        m.write_pos = state.write_pos;
+        m.write_count = state.write_count;
+        m.occupied = state.occupied;
+        Ok(m)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl KVMemState {
+    /// Compact JSON with lexicographically sorted keys and f64 values as
+    /// round-trippable strings: the same canonicalization as
+    /// `json.dumps(obj, sort_keys=True, separators=(",", ":"), ensure_ascii=False)`
+    /// in `scripts/make_manifest.py`.
+    pub fn to_canonical_json(&self) -> String {
+        // serde_json::Value objects are BTreeMaps, so going through Value sorts every key.
+        serde_json::to_value(self).expect("KVMemState is always representable as JSON").to_string()
+    }
+
+    pub fn from_json(s: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl KVMemV0 {
+    pub fn to_canonical_json(&self) -> String {
+        self.to_state().to_canonical_json()
+    }
+
+    pub fn from_json(s: &str) -> serde_json::Result<Self> {
+        let state = KVMemState::from_json(s)?;
+        KVMemV0::from_state(state).map_err(<serde_json::Error as serde::de::Error>::custom)
+    }
+}
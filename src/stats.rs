@@ -0,0 +1,79 @@
+/// Cumulative counters since construction or `KVMemV0::reset_stats`.
+///
+/// Diagnostics only: stats are not part of the memory state, so they are not
+/// hashed, snapshotted or serialized. Reads through `query`/`query_batch` take
+/// `&self` and are not counted; `step` reads are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KVMemStats {
+    /// Tokens ingested (`ingest` or any `step` variant).
+    pub steps: u64,
+    /// Tokens ingested with `write_event = true`.
+    pub write_events: u64,
+    pub reuse_updates: u64,
+    pub novel_writes: u64,
+    /// Writes dropped by the novelty gate.
+    pub gated: u64,
+    /// Novel writes that overwrote an occupied slot.
+    pub evictions: u64,
+    /// Occupied slots freed by `slot_ttl`.
+    pub expirations: u64,
+    /// Write events that compared against occupied memory slots, with the sum and max of
+    /// their best cosine similarity.
+    pub similarity_samples: u64,
+    pub similarity_sum: f64,
+    pub similarity_max: f64,
+    /// `step` reads, with the summed softmax mass on memory slots and on the window.
+    pub reads: u64,
+    pub memory_mass_sum: f64,
+    pub window_mass_sum: f64,
+}
+
+impl Default for KVMemStats {
+    fn default() -> Self {
+        Self {
+            steps: 0,
+            write_events: 0,
+            reuse_updates: 0,
+            novel_writes: 0,
+            gated: 0,
+            evictions: 0,
+            expirations: 0,
+            similarity_samples: 0,
+            similarity_sum: 0.0,
+            similarity_max: f64::NEG_INFINITY,
+            reads: 0,
+            memory_mass_sum: 0.0,
+            window_mass_sum: 0.0,
+        }
+    }
+}
+
+impl KVMemStats {
+    /// Mean best similarity seen by the write path.
+    pub fn mean_similarity(&self) -> Option<f64> {
+        (self.similarity_samples > 0).then(|| self.similarity_sum / self.similarity_samples as f64)
+    }
+
+    pub fn max_similarity(&self) -> Option<f64> {
+        (self.similarity_samples > 0).then_some(self.similarity_max)
+    }
+
+    /// Mean attention mass on memory slots per `step` read.
+    pub fn mean_memory_mass(&self) -> Option<f64> {
+        (self.reads > 0).then(|| self.memory_mass_sum / self.reads as f64)
+    }
+
+    /// Mean attention mass on window tokens per `step` read.
+    pub fn mean_window_mass(&self) -> Option<f64> {
+        (self.reads > 0).then(|| self.window_mass_sum / self.reads as f64)
+    }
+
+    pub(crate) fn record_similarity(&mut self, s: f64) {
+        // NaN inputs score -inf in `cosine`; keep them out of the mean.
+        if s.is_finite() {
+            self.similarity_samples += 1;
+            self.similarity_sum += s;
+            self.similarity_max = self.similarity_max.max(s);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{KVMemV0, Scalar, WriteOutcome};
+
+/// Where an attention key came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyOrigin {
+    /// Window position, 0 = oldest token in the window.
+    Window(usize),
+    /// Memory slot index.
+    Memory(usize),
+}
+
+/// One read, broken down over the attention domain (window oldest-to-newest,
+/// then memory slots). `weights` are the final read weights over the domain:
+/// `softmax(scores / scale)`, less any `alibi` penalty, under `ReadMode::Joint`;
+/// under the gated modes, separate window and memory softmaxes scaled by the
+/// gate. Unoccupied slots and slots outside `memory_top_k` get zero weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttentionTrace<S: Scalar = f64> {
+    pub output: Vec<S>,
+    pub origins: Vec<KeyOrigin>,
+    /// Raw dot products q . k (keys rotated when `rope` is set), before scaling.
+    pub scores: Vec<f64>,
+    pub weights: Vec<f64>,
+    pub scale: f64,
+}
+
+impl<S: Scalar> AttentionTrace<S> {
+    /// Origin with the largest weight; ties go to the earliest entry.
+    pub fn argmax(&self) -> Option<KeyOrigin> {
+        let mut best: Option<(usize, f64)> = None;
+        for (i, &w) in self.weights.iter().enumerate() {
+            if best.is_none_or(|(_, bw)| w > bw) {
+                best = Some((i, w));
+            }
+        }
+        best.map(|(i, _)| self.origins[i])
+    }
+
+    pub fn weight_of(&self, origin: KeyOrigin) -> Option<f64> {
+        self.origins.iter().position(|&o| o == origin).map(|i| self.weights[i])
+    }
+
+    /// Total weight on window tokens.
+    pub fn window_mass(&self) -> f64 {
+        self.mass(|o| matches!(o, KeyOrigin::Window(_)))
+    }
+
+    /// Total weight on memory slots.
+    pub fn memory_mass(&self) -> f64 {
+        self.mass(|o| matches!(o, KeyOrigin::Memory(_)))
+    }
+
+    fn mass(&self, f: impl Fn(KeyOrigin) -> bool) -> f64 {
+        self.origins.iter().zip(&self.weights).filter(|(&o, _)| f(o)).map(|(_, w)| w).sum()
+    }
+}
+
+/// What one `step` did: the write-path branch, then the read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepTrace<S: Scalar = f64> {
+    pub write: WriteOutcome,
+    pub read: AttentionTrace<S>,
+}
+
+impl<S: Scalar> KVMemV0<S> {
+    fn trace_read(&self, q: &[S]) -> AttentionTrace<S> {
+        let n = self.domain_len();
+        let mut scores = vec![0.0; n];
+        self.scores_into(q, &mut scores);
+        let mut weights = scores.clone();
+        self.weights_in_place(&mut weights, &mut Vec::new(), self.alibi);
+        let mut output = vec![S::default(); self.d];
+        self.mix_into(&weights, &mut output);
+        if let Some(o) = &self.observer {
+            o.on_read(q, &weights, &output);
+        }
+
+        let n_window = self.window_len();
+        let origins = (0..n)
+            .map(|i| if i < n_window { KeyOrigin::Window(i) } else { KeyOrigin::Memory(i - n_window) })
+            .collect();
+        AttentionTrace { output, origins, scores, weights, scale: self.attention_scale() }
+    }
+
+    /// `query` with the full attention breakdown; `output` equals `query(q)`.
+    pub fn query_explain(&self, q: &[S]) -> AttentionTrace<S> {
+        self.trace_read(q)
+    }
+
+    /// `step` reporting its write outcome and the full attention breakdown of its
+    /// read; `read.output` equals what `step` would have returned, and state
+    /// changes identically.
+    pub fn step_with_trace(&mut self, q: &[S], k: &[S], v: &[S], write_event: bool) -> StepTrace<S> {
+        let write = self.ingest(k, v, write_event);
+        let read = self.trace_read(q);
+        self.record_read(&read.weights);
+        StepTrace { write, read }
+    }
+}
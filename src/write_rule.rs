@@ -0,0 +1,123 @@
+use crate::Scalar;
+
+/// How a memory write (reuse or novel) folds `k`, `v` into a slot, with
+/// `g = g_write`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WriteRule {
+    /// `(1 - g) * old + g * new` for key and value (v0).
+    #[default]
+    Ema,
+    /// Key and value replaced by `k`, `v`; `g` is ignored.
+    Overwrite,
+    /// Key as `Ema`; value corrected by `g * (v - read(k))`, where `read(k)` is
+    /// the memory-only attention readout for query `k` before the write.
+    Delta,
+    /// Key and value are the mean of every write since the slot's last novel
+    /// write (see `KVMemV0::slot_write_counts`); `g` is ignored.
+    RunningMean,
+    /// `Ema`, rescaled so the result's norm is the larger of the old and new
+    /// norms: averaging does not shrink the slot.
+    MaxNorm,
+}
+
+impl WriteRule {
+    pub(crate) fn tag(self) -> u8 {
+        match self {
+            WriteRule::Ema => 0,
+            WriteRule::Overwrite => 1,
+            WriteRule::Delta => 2,
+            WriteRule::RunningMean => 3,
+            WriteRule::MaxNorm => 4,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(WriteRule::Ema),
+            1 => Some(WriteRule::Overwrite),
+            2 => Some(WriteRule::Delta),
+            3 => Some(WriteRule::RunningMean),
+            4 => Some(WriteRule::MaxNorm),
+            _ => None,
+        }
+    }
+
+    // Updates one slot row (key or value) in place. `count` includes this
+    // write; `read` is the delta rule's prediction, passed for the value row only.
+    pub(crate) fn update<S: Scalar>(self, dst: &mut [S], src: &[S], g: f64, count: u64, read: Option<&[f64]>) {
+        match (self, read) {
+            (WriteRule::Ema, _) | (WriteRule::Delta, None) => ema(dst, src, g),
+            (WriteRule::Overwrite, _) => dst.copy_from_slice(src),
+            (WriteRule::Delta, Some(read)) => {
+                for ((x, &y), &r) in dst.iter_mut().zip(src).zip(read) {
+                    *x = S::from_f64(x.to_f64() + g * (y.to_f64() - r));
+                }
+            }
+            (WriteRule::RunningMean, _) => ema(dst, src, 1.0 / count as f64),
+            (WriteRule::MaxNorm, _) => max_norm(dst, src, g),
+        }
+    }
+}
+
+fn ema<S: Scalar>(dst: &mut [S], src: &[S], g: f64) {
+    for (x, &y) in dst.iter_mut().zip(src) {
+        *x = S::from_f64((1.0 - g) * x.to_f64() + g * y.to_f64());
+    }
+}
+
+fn norm<S: Scalar>(x: &[S]) -> f64 {
+    x.iter().map(|v| v.to_f64() * v.to_f64()).sum::<f64>().sqrt()
+}
+
+fn max_norm<S: Scalar>(dst: &mut [S], src: &[S], g: f64) {
+    let target = norm(dst).max(norm(src));
+    // The mix is computed twice, for its norm and then in place, so nothing is allocated.
+    let mix = |x: S, y: S| (1.0 - g) * x.to_f64() + g * y.to_f64();
+    let n = dst.iter().zip(src).map(|(&x, &y)| mix(x, y) * mix(x, y)).sum::<f64>().sqrt();
+    let s = if n > 0.0 { target / n } else { 1.0 };
+    for (x, &y) in dst.iter_mut().zip(src) {
+        *x = S::from_f64(mix(*x, y) * s);
+    }
+}
+
+/// Separate write gains for keys and values on the reuse and novel paths, in
+/// place of `g_write` for all four. Each must be in (0, 1] like `g_write`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WriteGains {
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub key_reuse: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub value_reuse: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub key_novel: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::repr::f64_str"))]
+    pub value_novel: f64,
+}
+
+impl WriteGains {
+    /// `g_key` for keys and `g_value` for values on both paths.
+    pub fn split(g_key: f64, g_value: f64) -> Self {
+        WriteGains { key_reuse: g_key, value_reuse: g_value, key_novel: g_key, value_novel: g_value }
+    }
+
+    pub(crate) fn named(self) -> [(&'static str, f64); 4] {
+        [
+            ("g_key_reuse", self.key_reuse),
+            ("g_value_reuse", self.value_reuse),
+            ("g_key_novel", self.key_novel),
+            ("g_value_novel", self.value_novel),
+        ]
+    }
+
+    pub(crate) fn bits(self) -> [u64; 4] {
+        self.named().map(|(_, x)| x.to_bits())
+    }
+
+    // (key gain, value gain) for a reuse or novel write.
+    pub(crate) fn for_path(self, novel: bool) -> (f64, f64) {
+        if novel { (self.key_novel, self.value_novel) } else { (self.key_reuse, self.value_reuse) }
+    }
+}
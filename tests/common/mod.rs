@@ -0,0 +1,86 @@
+//! Fixtures shared by the integration tests; each test file declares `mod common;`.
+#![allow(dead_code)]
+
+use kv_memory_vsc_v0::{KVMemConfig, KVMemConfigBuilder, KVMemV0};
+
+/// `s` times basis vector `i` in `d` dimensions.
+pub fn e(d: usize, i: usize, s: f64) -> Vec<f64> {
+    let mut v = vec![0.0; d];
+    v[i] = s;
+    v
+}
+
+pub fn cat(a: &[f64], b: &[f64]) -> Vec<f64> {
+    a.iter().chain(b.iter()).cloned().collect()
+}
+
+/// Config builder with the window, slot count and dimension set; everything else default.
+pub fn builder(l_window: usize, m_slots: usize, d: usize) -> KVMemConfigBuilder {
+    KVMemConfig::builder().l_window(l_window).m_slots(m_slots).d(d)
+}
+
+/// Memory built from a builder that must validate.
+pub fn build(b: KVMemConfigBuilder) -> KVMemV0 {
+    KVMemV0::from_config(&b.build().unwrap()).unwrap()
+}
+
+pub fn max_abs(x: &[f64]) -> f64 {
+    x.iter().fold(0.0, |a, &b| a.max(b.abs()))
+}
+
+/// Index of the largest |x|; ties go to the lowest index.
+pub fn argmax_abs(x: &[f64]) -> usize {
+    let mut bi = 0usize;
+    let mut bv = f64::NEG_INFINITY;
+    for (i, &v) in x.iter().enumerate() {
+        let a = v.abs();
+        if a > bv {
+            bv = a;
+            bi = i;
+        }
+    }
+    bi
+}
+
+/// HIT: the read's largest coordinate is `expect_idx` and exceeds `thr`.
+pub fn is_hit(out: &[f64], expect_idx: usize, thr: f64) -> bool {
+    max_abs(out) > thr && argmax_abs(out) == expect_idx
+}
+
+// FACT / FILL / ASK tokens of the capacity benchmark.
+
+pub fn write_fact(m: &mut KVMemV0, d: usize, idx: usize) {
+    let k = e(d, idx, 10.0);
+    let v = e(d, idx, 100.0);
+    let q = k.clone();
+    let _ = m.step(q, k, v, true);
+}
+
+pub fn fill(m: &mut KVMemV0, d: usize, n: usize) {
+    let z = vec![0.0; d];
+    for _ in 0..n {
+        let _ = m.step(z.clone(), z.clone(), z.clone(), false);
+    }
+}
+
+pub fn ask(m: &mut KVMemV0, d: usize, idx: usize) -> Vec<f64> {
+    let q = e(d, idx, 10.0);
+    let z = vec![0.0; d];
+    m.step(q, z.clone(), z, false)
+}
+
+/// xorshift64 stream of reals in [-1, 1).
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+
+    pub fn vec(&mut self, d: usize) -> Vec<f64> {
+        (0..d).map(|_| self.next()).collect()
+    }
+}
@@ -0,0 +1,82 @@
+use kv_memory_vsc_v0::{Alibi, KVMemConfig, KVMemError, KVMemV0, KeyOrigin, MemoryBias, MultiHeadKVMem};
+
+mod common;
+use common::{builder, e};
+
+const D: usize = 4;
+
+fn cfg(l_window: usize, m_slots: usize, alibi: Option<Alibi>) -> KVMemConfig {
+    builder(l_window, m_slots, D).alibi(alibi).build().unwrap()
+}
+
+#[test]
+fn window_penalty_is_linear_in_distance() {
+    let mut m = KVMemV0::from_config(&cfg(3, 0, Some(Alibi::new(0.5)))).unwrap();
+    for i in 0..3 {
+        m.ingest(&e(D, 0, 1.0), &e(D, i, 1.0), false);
+    }
+    let t = m.query_explain(&e(D, 0, 1.0));
+    assert_eq!(t.argmax(), Some(KeyOrigin::Window(2)));
+    for i in 0..2 {
+        assert!((t.weights[i + 1] / t.weights[i] - 0.5f64.exp()).abs() < 1e-12);
+    }
+    assert_eq!(t.output, m.query(&e(D, 0, 1.0)));
+}
+
+#[test]
+fn memory_is_exempt_or_penalized_by_age() {
+    let run = |memory| {
+        let mut m = KVMemV0::from_config(&cfg(1, 1, Some(Alibi { slope: 0.5, memory }))).unwrap();
+        m.ingest(&e(D, 0, 2.0), &e(D, 0, 1.0), true);
+        for _ in 0..3 {
+            m.ingest(&e(D, 1, 2.0), &e(D, 1, 1.0), false);
+        }
+        assert_eq!(m.slot_ages(), &[3]);
+        m.query_explain(&e(D, 0, 2.0)).weight_of(KeyOrigin::Memory(0)).unwrap()
+    };
+    // Logits: window 0, slot 4 / sqrt(4) = 2, less 0.5 * age 3 under Age.
+    let sigmoid = |x: f64| 1.0 / (1.0 + (-x).exp());
+    assert!((run(MemoryBias::Exempt) - sigmoid(2.0)).abs() < 1e-12);
+    assert!((run(MemoryBias::Age) - sigmoid(0.5)).abs() < 1e-12);
+}
+
+#[test]
+fn per_head_slopes_match_single_head_memories() {
+    let base = cfg(4, 1, Some(Alibi { slope: 1.0, memory: MemoryBias::Age }));
+    let slopes = Alibi::geometric_slopes(2);
+    assert_eq!(slopes, vec![1.0 / 16.0, 1.0 / 256.0]);
+    let mut mh = MultiHeadKVMem::grouped(2, 1, &base).unwrap().with_alibi_slopes(&slopes).unwrap();
+    let mut heads: Vec<_> = slopes
+        .iter()
+        .map(|&slope| KVMemV0::from_config(&KVMemConfig { alibi: Some(Alibi { slope, memory: MemoryBias::Age }), ..base.clone() }).unwrap())
+        .collect();
+
+    for t in 0..6 {
+        let (k, v) = (e(D, t % 3, 2.0), e(D, t % 4, 1.0));
+        let q: Vec<f64> = [e(D, 0, 2.0), e(D, 1, 2.0)].concat();
+        let out = mh.step(q.clone(), k.clone(), v.clone(), true);
+        let want: Vec<f64> = heads.iter_mut().enumerate().flat_map(|(h, m)| m.step(q[h * D..(h + 1) * D].to_vec(), k.clone(), v.clone(), true)).collect();
+        assert_eq!(out, want);
+        assert_eq!(mh.query(&q), out);
+    }
+    assert_eq!(mh.head_alibi(1), Some(Alibi { slope: 1.0 / 256.0, memory: MemoryBias::Age }));
+    assert_ne!(mh.state_sha256(), MultiHeadKVMem::grouped(2, 1, &base).unwrap().state_sha256());
+
+    let wrong = MultiHeadKVMem::new(2, &base).unwrap().with_alibi_slopes(&[0.5]);
+    assert!(matches!(wrong, Err(KVMemError::DimensionMismatch { name: "alibi_slopes", expected: 2, got: 1 })));
+}
+
+#[test]
+fn alibi_is_hashed_and_persisted() {
+    let mut m = KVMemV0::from_config(&cfg(2, 2, Some(Alibi { slope: 0.25, memory: MemoryBias::Age }))).unwrap();
+    for i in 0..5 {
+        m.ingest(&e(D, i % 3, 3.0), &e(D, i % 4, 1.0), true);
+    }
+    assert_ne!(m.config().sha256(), cfg(2, 2, None).sha256());
+    let restored = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    assert_eq!(restored.state_sha256(), m.state_sha256());
+    assert_eq!(restored.query(&e(D, 1, 3.0)), m.query(&e(D, 1, 3.0)));
+
+    let bad = KVMemConfig::builder().alibi(Some(Alibi::new(-1.0))).build();
+    assert!(matches!(bad, Err(KVMemError::NotPositive { name: "alibi_slope", .. })));
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "ann")]
+
+use kv_memory_vsc_v0::{KVMemError, KVMemV0, LshConfig};
+
+mod common;
+use common::{Rng, build, builder};
+
+fn mem(m_slots: usize) -> KVMemV0 {
+    build(builder(4, m_slots, 16).tau_reuse(0.9).tau_novel(0.5).g_write(0.5))
+}
+
+fn run(m: &mut KVMemV0, seed: u64, n: usize) {
+    let mut rng = Rng(seed);
+    for _ in 0..n {
+        let k = rng.vec(16);
+        m.ingest(&k, &k, true);
+    }
+}
+
+#[test]
+fn index_stays_in_sync_with_slot_writes() {
+    let mut m = mem(64);
+    m.enable_lsh(LshConfig::default()).unwrap();
+    run(&mut m, 11, 300);
+
+    // Every stored key hashes into its own slot's buckets, so it is found exactly.
+    let state = m.to_state();
+    for (j, key) in state.km.iter().enumerate() {
+        let (found, sim) = m.nearest_slot(key).unwrap();
+        assert!((sim - 1.0).abs() < 1e-12, "slot {} found {} with {}", j, found, sim);
+        assert_eq!(m.nearest_slot_exact(key).unwrap().1, sim);
+    }
+}
+
+#[test]
+fn lsh_is_deterministic_for_a_seed() {
+    let mut a = mem(64);
+    let mut b = mem(64);
+    a.enable_lsh(LshConfig { seed: 7, ..LshConfig::default() }).unwrap();
+    b.enable_lsh(LshConfig { seed: 7, ..LshConfig::default() }).unwrap();
+    run(&mut a, 3, 200);
+    run(&mut b, 3, 200);
+    assert_eq!(a.state_sha256(), b.state_sha256());
+    assert_eq!(a.lsh_config().map(|c| c.seed), Some(7));
+}
+
+#[test]
+fn enabling_on_a_filled_memory_and_disabling_restores_exact_search() {
+    let mut m = mem(32);
+    run(&mut m, 5, 100);
+    m.enable_lsh(LshConfig { tables: 4, bits: 4, seed: 1 }).unwrap();
+    let mut exact = m.clone();
+    exact.disable_lsh();
+    assert_eq!(exact.lsh_config(), None);
+
+    let mut rng = Rng(99);
+    for _ in 0..50 {
+        let k = rng.vec(16);
+        assert_eq!(exact.nearest_slot(&k), exact.nearest_slot_exact(&k));
+    }
+
+    assert_eq!(m.enable_lsh(LshConfig { bits: 65, ..LshConfig::default() }), Err(KVMemError::IndexConfig("lsh bits must be in 1..=64")));
+}
@@ -1,51 +1,7 @@
-use kv_memory_vsc_v0::KVMemV0;
+use kv_memory_vsc_v0::{KVMemConfig, KVMemError, KVMemV0, ReadRefresh};
 
-fn e(d: usize, i: usize, s: f64) -> Vec<f64> {
-    let mut v = vec![0.0; d];
-    v[i] = s;
-    v
-}
-
-fn max_abs(x: &[f64]) -> f64 {
-    x.iter().fold(0.0, |a, &b| a.max(b.abs()))
-}
-
-fn argmax_abs(x: &[f64]) -> usize {
-    let mut bi = 0usize;
-    let mut bv = f64::NEG_INFINITY;
-    for (i, &v) in x.iter().enumerate() {
-        let a = v.abs();
-        if a > bv {
-            bv = a;
-            bi = i;
-        }
-    }
-    bi
-}
-
-fn write_fact(m: &mut KVMemV0, d: usize, idx: usize) {
-    let k = e(d, idx, 10.0);
-    let v = e(d, idx, 100.0);
-    let q = k.clone();
-    let _ = m.step(q, k, v, true);
-}
-
-fn fill(m: &mut KVMemV0, d: usize, n: usize) {
-    let z = vec![0.0; d];
-    for _ in 0..n {
-        let _ = m.step(z.clone(), z.clone(), z.clone(), false);
-    }
-}
-
-fn ask(m: &mut KVMemV0, d: usize, idx: usize) -> Vec<f64> {
-    let q = e(d, idx, 10.0);
-    let z = vec![0.0; d];
-    m.step(q, z.clone(), z, false)
-}
-
-fn is_hit(out: &[f64], expect_idx: usize, thr: f64) -> bool {
-    max_abs(out) > thr && argmax_abs(out) == expect_idx
-}
+mod common;
+use common::{argmax_abs, ask, build, builder, fill, is_hit, max_abs, write_fact};
 
 #[test]
 fn baseline_fails_all_facts_under_saturation() {
@@ -101,6 +57,65 @@ assert!(max_abs(&out1) > 5.0);
     assert_eq!(argmax_abs(&out2), 2);
 }
 
+fn m2(read_refresh: ReadRefresh) -> KVMemV0 {
+    build(builder(8, 2, 8).tau_reuse(0.85).tau_novel(0.5).g_write(1.0).read_refresh(read_refresh))
+}
+
+// Slot pressure as in memory_m2_only_slot_pressure_remains, but fact A is recalled
+// (from memory, outside the window) before each later write.
+fn recalled_a_under_slot_pressure(m: &mut KVMemV0) -> [bool; 3] {
+    let (d, l) = (8usize, 8usize);
+    write_fact(m, d, 0);
+    fill(m, d, l);
+    assert!(is_hit(&ask(m, d, 0), 0, 5.0));
+    write_fact(m, d, 1);
+    fill(m, d, l);
+    assert!(is_hit(&ask(m, d, 0), 0, 5.0));
+    write_fact(m, d, 2);
+    fill(m, d, 64);
+    [is_hit(&ask(m, d, 0), 0, 5.0), is_hit(&ask(m, d, 1), 1, 5.0), is_hit(&ask(m, d, 2), 2, 5.0)]
+}
+
+#[test]
+fn recalled_fact_is_evicted_without_read_refresh() {
+    let mut m = m2(ReadRefresh::Off);
+    assert_eq!(recalled_a_under_slot_pressure(&mut m), [false, true, true]);
+}
+
+#[test]
+fn recalled_fact_survives_with_read_refresh() {
+    let mut m = m2(ReadRefresh::Reset);
+    assert_eq!(recalled_a_under_slot_pressure(&mut m), [true, false, true]);
+
+    let mut m = m2(ReadRefresh::Decrement(8));
+    assert_eq!(recalled_a_under_slot_pressure(&mut m), [true, false, true]);
+}
+
+#[test]
+fn read_hits_count_dominant_reads_only() {
+    let d = 8usize;
+    let mut m = m2(ReadRefresh::Decrement(3));
+    write_fact(&mut m, d, 0);
+    fill(&mut m, d, 8);
+    // The write's own query splits its weight between the window copy and the slot, the
+    // empty slot 1 taking none: a hit at exactly the threshold.
+    assert_eq!(m.read_hits(), &[1, 0]);
+    assert_eq!(m.slot_ages(), &[8, 9]);
+
+    let _ = ask(&mut m, d, 0);
+    assert_eq!(m.read_hits(), &[2, 0]);
+    assert_eq!(m.slot_ages(), &[6, 10]);
+
+    let _ = ask(&mut m, d, 5); // orthogonal query: weight spreads, no hit
+    assert_eq!(m.read_hits(), &[2, 0]);
+    assert_eq!(m.slot_ages(), &[7, 11]);
+
+    assert_eq!(
+        KVMemConfig::builder().read_hit_threshold(1.5).build().err(),
+        Some(KVMemError::WeightOutOfRange { name: "read_hit_threshold", value: 1.5 })
+    );
+}
+
 #[test]
 fn memory_m3_keeps_three_facts_under_saturation() {
     let d = 8usize;
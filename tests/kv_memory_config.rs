@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+
+use kv_memory_vsc_v0::{KVMemConfig, KVMemError, KVMemV0};
+
+#[test]
+fn defaults_match_pinned_manifest_params() {
+    let cfg = KVMemConfig::default();
+    assert_eq!(cfg.l_window, 8);
+    assert_eq!(cfg.m_slots, 1);
+    assert_eq!(cfg.d, 2);
+    assert_eq!(cfg.tau_reuse, 0.9);
+    assert_eq!(cfg.tau_novel, 0.5);
+    assert_eq!(cfg.g_write, 1.0);
+    assert_eq!(KVMemConfig::builder().build().unwrap(), cfg);
+}
+
+#[test]
+fn builder_validates_like_try_new() {
+    assert_eq!(
+        KVMemConfig::builder().tau_novel(-1.5).build().err(),
+        Some(KVMemError::ThresholdOutOfRange { name: "tau_novel", value: -1.5 })
+    );
+    assert_eq!(
+        KVMemConfig::builder().l_window(0).m_slots(0).build().err(),
+        Some(KVMemError::EmptyAttentionDomain)
+    );
+
+    let cfg = KVMemConfig { g_write: 2.0, ..KVMemConfig::default() };
+    assert!(KVMemV0::from_config(&cfg).is_err());
+}
+
+#[test]
+fn from_config_matches_positional_constructor() {
+    let cfg = KVMemConfig::builder()
+        .l_window(8)
+        .m_slots(1)
+        .d(2)
+        .tau_reuse(0.85)
+        .tau_novel(0.5)
+        .g_write(1.0)
+        .build()
+        .unwrap();
+
+    let mut a = KVMemV0::from_config(&cfg).unwrap();
+    let mut b = KVMemV0::new(8, 1, 2, 0.85, 0.5, 1.0);
+    assert_eq!(a.config(), cfg);
+    assert_eq!(a.state_sha256(), b.state_sha256());
+
+    let oa = a.step(vec![0.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0], true);
+    let ob = b.step(vec![0.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0], true);
+    assert_eq!(oa, ob);
+    assert_eq!(a.state_sha256(), b.state_sha256());
+}
+
+#[test]
+fn configs_compare_and_hash_independently_of_state() {
+    let a = KVMemConfig::builder().tau_reuse(0.85).build().unwrap();
+    let b = KVMemConfig::builder().tau_reuse(0.85).build().unwrap();
+    let c = KVMemConfig::builder().tau_reuse(0.85).m_slots(2).build().unwrap();
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a.sha256(), b.sha256());
+    assert_ne!(a.sha256(), c.sha256());
+
+    let set: HashSet<KVMemConfig> = [a.clone(), b, c].into_iter().collect();
+    assert_eq!(set.len(), 2);
+
+    let mut m = KVMemV0::from_config(&a).unwrap();
+    let _ = m.step(vec![0.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0], true);
+    assert_eq!(m.config().sha256(), a.sha256());
+}
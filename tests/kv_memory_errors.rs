@@ -0,0 +1,89 @@
+use kv_memory_vsc_v0::{KVMemError, KVMemV0, MultiHeadKVMem, WriteOutcome};
+
+mod common;
+use common::builder;
+
+#[test]
+fn try_new_rejects_bad_config() {
+    assert_eq!(KVMemV0::try_new(8, 1, 0, 0.85, 0.5, 1.0).err(), Some(KVMemError::ZeroDimension));
+    assert_eq!(KVMemV0::try_new(0, 0, 2, 0.85, 0.5, 1.0).err(), Some(KVMemError::EmptyAttentionDomain));
+    assert_eq!(
+        KVMemV0::try_new(8, 1, 2, 1.5, 0.5, 1.0).err(),
+        Some(KVMemError::ThresholdOutOfRange { name: "tau_reuse", value: 1.5 })
+    );
+    assert!(matches!(
+        KVMemV0::try_new(8, 1, 2, 0.85, f64::NAN, 1.0),
+        Err(KVMemError::ThresholdOutOfRange { name: "tau_novel", .. })
+    ));
+    assert_eq!(
+        KVMemV0::try_new(8, 1, 2, 0.85, 0.5, 0.0).err(),
+        Some(KVMemError::GainOutOfRange { name: "g_write", value: 0.0 })
+    );
+    assert!(KVMemV0::try_new(0, 1, 2, 0.85, 0.5, 1.0).is_ok());
+
+    // Buffer sizes that overflow are rejected before anything is allocated.
+    assert_eq!(
+        KVMemV0::try_new(usize::MAX / 2, 0, 4, 0.85, 0.5, 1.0).err(),
+        Some(KVMemError::SizeOverflow { name: "l_window * d" })
+    );
+    assert_eq!(
+        KVMemV0::try_new(8, 1 << 62, 2, 0.85, 0.5, 1.0).err(),
+        Some(KVMemError::SizeOverflow { name: "m_slots * d" })
+    );
+}
+
+#[test]
+fn try_step_rejects_bad_inputs_without_mutating() {
+    let mut m = KVMemV0::try_new(8, 1, 2, 0.85, 0.5, 1.0).unwrap();
+    let h0 = m.state_sha256();
+
+    assert_eq!(
+        m.try_step(vec![0.0; 3], vec![1.0, 0.0], vec![0.0, 1.0], true).err(),
+        Some(KVMemError::DimensionMismatch { name: "q", expected: 2, got: 3 })
+    );
+    assert_eq!(
+        m.try_step(vec![0.0; 2], vec![1.0], vec![0.0, 1.0], true).err(),
+        Some(KVMemError::DimensionMismatch { name: "k", expected: 2, got: 1 })
+    );
+    assert_eq!(
+        m.try_step(vec![0.0; 2], vec![1.0, 0.0], vec![0.0, f64::INFINITY], true).err(),
+        Some(KVMemError::NonFiniteInput { name: "v", index: 1 })
+    );
+    assert_eq!(m.state_sha256(), h0);
+
+    let out = m.try_step(vec![0.0; 2], vec![1.0, 0.0], vec![0.0, 1.0], true).unwrap();
+    assert_eq!(out.len(), 2);
+    assert_ne!(m.state_sha256(), h0);
+}
+
+#[test]
+fn try_ingest_and_try_query_validate_inputs() {
+    let mut m = KVMemV0::try_new(8, 1, 2, 0.85, 0.5, 1.0).unwrap();
+    let h0 = m.state_sha256();
+    assert_eq!(
+        m.try_ingest(&[1.0, 0.0, 0.0], &[0.0, 1.0], true).err(),
+        Some(KVMemError::DimensionMismatch { name: "k", expected: 2, got: 3 })
+    );
+    assert_eq!(m.try_ingest(&[1.0, 0.0], &[f64::NAN, 1.0], true).err(), Some(KVMemError::NonFiniteInput { name: "v", index: 0 }));
+    assert_eq!(m.state_sha256(), h0);
+    assert!(matches!(m.try_ingest(&[1.0, 0.0], &[0.0, 1.0], true), Ok(WriteOutcome::Written { slot: 0, .. })));
+
+    assert_eq!(m.try_query(&[1.0]).err(), Some(KVMemError::DimensionMismatch { name: "q", expected: 2, got: 1 }));
+    assert_eq!(m.try_query(&[1.0, 0.0]).unwrap(), m.query(&[1.0, 0.0]));
+
+    let cfg = builder(2, 1, 2).build().unwrap();
+    let mut mh = MultiHeadKVMem::grouped(2, 1, &cfg).unwrap();
+    assert_eq!(
+        mh.try_ingest(&[1.0, 0.0], &[0.0; 4], true).err(),
+        Some(KVMemError::DimensionMismatch { name: "v", expected: 2, got: 4 })
+    );
+    assert_eq!(mh.try_query(&[0.0, f64::INFINITY, 0.0, 0.0]).err(), Some(KVMemError::NonFiniteInput { name: "q", index: 1 }));
+    assert_eq!(mh.try_ingest(&[1.0, 0.0], &[0.0, 1.0], true).unwrap().len(), 1);
+    assert_eq!(mh.try_query(&[1.0, 0.0, 0.0, 1.0]).unwrap().len(), 4);
+}
+
+#[test]
+#[should_panic(expected = "tau_reuse")]
+fn new_still_panics_on_bad_config() {
+    let _ = KVMemV0::new(8, 1, 2, -2.0, 0.5, 1.0);
+}
@@ -1,28 +1,7 @@
-use kv_memory_vsc_v0::KVMemV0;
+use kv_memory_vsc_v0::{KVMemConfig, KVMemError, KVMemV0, WriteGains};
 
-fn max_abs(x: &[f64]) -> f64 {
-    x.iter().map(|v| v.abs()).fold(0.0, f64::max)
-}
-fn argmax_abs(x: &[f64]) -> usize {
-    let mut bi = 0usize;
-    let mut bv = -1.0f64;
-    for (i, v) in x.iter().enumerate() {
-        let a = v.abs();
-        if a > bv {
-            bv = a;
-            bi = i;
-        }
-    }
-    bi
-}
-fn is_hit(out: &[f64], idx: usize, thr: f64) -> bool {
-    argmax_abs(out) == idx && max_abs(out) > thr
-}
-fn e(d: usize, idx: usize, s: f64) -> Vec<f64> {
-    let mut v = vec![0.0f64; d];
-    v[idx] = s;
-    v
-}
+mod common;
+use common::{builder, e, is_hit};
 
 #[test]
 fn baseline_misses_after_truncation_even_without_drift() {
@@ -131,3 +110,50 @@ fn memory_kv_sha256_changes_on_writes_not_on_reads() {
     let h4 = mem.memory_kv_sha256();
     assert_eq!(h3, h4);
 }
+
+#[test]
+fn split_gains_keep_keys_stable_while_values_decay() {
+    // Decay writes use a key at cosine ~0.96 to A (a reuse) and v=0. With one g_write the slot
+    // key drifts toward the decay key; storing A's key whole and refreshing keys with
+    // g_key=0.01 keeps it on A while values decay as before.
+    let d = 8usize;
+    let k_a = e(d, 0, 1.0);
+    let mut k_decay = e(d, 0, 1.0);
+    k_decay[1] = 0.3;
+
+    let run = |gains: Option<WriteGains>| {
+        let cfg = builder(1, 1, d).g_write(0.25).write_gains(gains).build().unwrap();
+        let mut mem = KVMemV0::from_config(&cfg).unwrap();
+        let _ = mem.step(vec![0.0; d], k_a.clone(), e(d, 0, 60.0), true);
+        for _ in 0..6 {
+            let _ = mem.step(vec![0.0; d], k_decay.clone(), vec![0.0; d], true);
+        }
+        let s = mem.to_state();
+        let key = &s.km[0];
+        (key[1] / key[0], s.vm[0][0])
+    };
+
+    let (drift_shared, v_shared) = run(None);
+    let (drift_split, v_split) = run(Some(WriteGains { key_reuse: 0.01, value_reuse: 0.25, key_novel: 1.0, value_novel: 0.25 }));
+
+    // Value: 15 * 0.75^6 either way.
+    let v6 = 15.0 * 0.75f64.powi(6);
+    assert!((v_shared - v6).abs() < 1e-9 && (v_split - v6).abs() < 1e-9);
+    // Key: off-axis component ~0.28 of the A component with one gain, under 0.02 when split.
+    assert!(drift_shared > 0.25);
+    assert!(drift_split < 0.02);
+
+    let bad = KVMemConfig::builder().write_gains(Some(WriteGains::split(0.0, 0.5))).build();
+    assert_eq!(bad.unwrap_err(), KVMemError::GainOutOfRange { name: "g_key_reuse", value: 0.0 });
+}
+
+#[test]
+fn write_gains_enter_memory_kv_sha256() {
+    let cfg = builder(1, 1, 8).g_write(0.25).build().unwrap();
+    let plain = KVMemV0::from_config(&cfg).unwrap();
+    // Same effective gains, but spelled out: the memory hash records the setting.
+    let split = KVMemV0::from_config(&KVMemConfig { write_gains: Some(WriteGains::split(0.25, 0.25)), ..cfg }).unwrap();
+    assert_ne!(plain.memory_kv_sha256(), split.memory_kv_sha256());
+    let restored = KVMemV0::from_snapshot_bytes(&split.to_snapshot_bytes()).unwrap();
+    assert_eq!(restored.memory_kv_sha256(), split.memory_kv_sha256());
+}
@@ -0,0 +1,142 @@
+use kv_memory_vsc_v0::{KVMemConfig, KVMemError, KVMemV0, MultiHeadKVMem, ReadRefresh, Replacement};
+
+mod common;
+use common::{builder, cat, e};
+
+fn head_cfg(d: usize) -> KVMemConfig {
+    builder(2, 2, d).tau_reuse(0.85).tau_novel(0.5).g_write(0.5).build().unwrap()
+}
+
+#[test]
+fn heads_match_independent_single_head_memories() {
+    let dh = 4usize;
+    let cfg = head_cfg(dh);
+    let mut mh = MultiHeadKVMem::new(2, &cfg).unwrap();
+    let mut h0 = KVMemV0::from_config(&cfg).unwrap();
+    let mut h1 = KVMemV0::from_config(&cfg).unwrap();
+    assert_eq!(mh.d_model(), 8);
+
+    for t in 0..10 {
+        let (q0, k0, v0) = (e(dh, t % 4, 1.0), e(dh, t % 3, 2.0), e(dh, t % 4, 5.0));
+        let (q1, k1, v1) = (e(dh, (t + 1) % 4, 3.0), e(dh, t % 2, -1.0), e(dh, 3, t as f64));
+        let w = t % 3 != 2;
+
+        let out = mh.step(cat(&q0, &q1), cat(&k0, &k1), cat(&v0, &v1), w);
+        let o0 = h0.step(q0, k0, v0, w);
+        let o1 = h1.step(q1, k1, v1, w);
+        assert_eq!(out, cat(&o0, &o1));
+    }
+
+    assert_eq!(mh.head(0).state_sha256(), h0.state_sha256());
+    assert_eq!(mh.head_memory_kv_sha256(1), h1.memory_kv_sha256());
+}
+
+#[test]
+fn novelty_gating_is_per_head() {
+    let dh = 2usize;
+    let mut mh = MultiHeadKVMem::new(2, &head_cfg(dh)).unwrap();
+    let z = vec![0.0; 2 * dh];
+
+    let _ = mh.step(z.clone(), vec![1.0, 0.0, 1.0, 0.0], vec![0.0, 1.0, 0.0, 1.0], true);
+    let (m0, m1, all) = (mh.head_memory_kv_sha256(0), mh.head_memory_kv_sha256(1), mh.memory_kv_sha256());
+
+    // Head 0 sees a key at cosine ~0.71 (gated); head 1 sees an orthogonal key (novel write).
+    let _ = mh.step(z, vec![1.0, 1.0, 0.0, 1.0], vec![0.0, 1.0, 0.0, 1.0], true);
+    assert_eq!(mh.head_memory_kv_sha256(0), m0);
+    assert_ne!(mh.head_memory_kv_sha256(1), m1);
+    assert_ne!(mh.memory_kv_sha256(), all);
+}
+
+#[test]
+fn combined_hash_is_deterministic_and_validates_inputs() {
+    let dh = 2usize;
+    let mut a = MultiHeadKVMem::new(3, &head_cfg(dh)).unwrap();
+    let mut b = MultiHeadKVMem::new(3, &head_cfg(dh)).unwrap();
+    for t in 0..5 {
+        let x: Vec<f64> = (0..6).map(|i| ((i + t) % 4) as f64).collect();
+        let _ = a.step(x.clone(), x.clone(), x.clone(), true);
+        let _ = b.step(x.clone(), x.clone(), x, true);
+    }
+    assert_eq!(a.state_sha256(), b.state_sha256());
+
+    assert_eq!(
+        a.try_step(vec![0.0; 5], vec![0.0; 6], vec![0.0; 6], false).err(),
+        Some(KVMemError::DimensionMismatch { name: "q", expected: 6, got: 5 })
+    );
+    assert_eq!(MultiHeadKVMem::new(0, &head_cfg(dh)).err(), Some(KVMemError::ZeroHeads));
+}
+
+#[test]
+fn grouped_query_writes_once_per_kv_head() {
+    let dh = 2usize;
+    let cfg = head_cfg(dh);
+    let mut gqa = MultiHeadKVMem::grouped(4, 2, &cfg).unwrap();
+    let mut g0 = KVMemV0::from_config(&cfg).unwrap();
+    let mut g1 = KVMemV0::from_config(&cfg).unwrap();
+    assert_eq!((gqa.d_model(), gqa.d_kv(), gqa.group_size()), (8, 4, 2));
+
+    for t in 0..8 {
+        let (k0, v0) = (vec![1.0, 0.1 * t as f64], vec![t as f64, 1.0]);
+        let (k1, v1) = (vec![(t % 2) as f64, 1.0], vec![-1.0, t as f64]);
+        let q: Vec<f64> = vec![1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0];
+
+        let out = gqa.step(q.clone(), cat(&k0, &k1), cat(&v0, &v1), true);
+        let o0 = g0.step(q[0..2].to_vec(), k0, v0, true);
+        let o1 = g1.step(q[4..6].to_vec(), k1, v1, true);
+
+        // Query heads in a group share one memory, so equal queries give equal outputs.
+        assert_eq!(&out[0..2], o0.as_slice());
+        assert_eq!(&out[2..4], o0.as_slice());
+        assert_eq!(&out[4..6], o1.as_slice());
+        assert_eq!(&out[6..8], o1.as_slice());
+    }
+
+    // With g_write < 1 a second reuse write per group would diverge from the single-head memory.
+    assert_eq!(gqa.head_memory_kv_sha256(0), g0.memory_kv_sha256());
+    assert_eq!(gqa.head_memory_kv_sha256(1), g1.memory_kv_sha256());
+    assert_eq!(gqa.head(3).state_sha256(), g1.state_sha256());
+}
+
+#[test]
+fn grouped_query_records_one_read_per_kv_head() {
+    // LFU and read refresh consume read_hits and ages; with equal queries across the group
+    // the mean weights equal one head's, so the shared memory tracks a single-head memory.
+    let dh = 2usize;
+    let cfg = KVMemConfig { read_refresh: ReadRefresh::Decrement(2), replacement: Replacement::Lfu, ..head_cfg(dh) };
+    let mut gqa = MultiHeadKVMem::grouped(3, 1, &cfg).unwrap();
+    let mut single = KVMemV0::from_config(&cfg).unwrap();
+
+    for t in 0..12 {
+        let q = e(dh, t % 2, 2.0);
+        let (k, v) = (e(dh, (t / 3) % 2, if t % 4 == 0 { -1.0 } else { 1.0 }), e(dh, t % 2, t as f64));
+        let out = gqa.step(cat(&cat(&q, &q), &q), k.clone(), v.clone(), t % 3 == 0);
+        let o = single.step(q, k, v, t % 3 == 0);
+        assert_eq!(out, cat(&cat(&o, &o), &o));
+    }
+
+    let head = gqa.kv_head(0);
+    assert_eq!(head.read_hits(), single.read_hits());
+    assert_eq!(head.slot_ages(), single.slot_ages());
+    assert_eq!(head.stats().reads, 12);
+    assert_eq!(head.state_sha256(), single.state_sha256());
+}
+
+#[test]
+fn grouped_query_rejects_bad_grouping() {
+    let cfg = head_cfg(2);
+    assert_eq!(
+        MultiHeadKVMem::grouped(4, 3, &cfg).err(),
+        Some(KVMemError::HeadGrouping { n_heads: 4, n_kv_heads: 3 })
+    );
+    assert_eq!(
+        MultiHeadKVMem::grouped(4, 0, &cfg).err(),
+        Some(KVMemError::HeadGrouping { n_heads: 4, n_kv_heads: 0 })
+    );
+
+    let mut gqa = MultiHeadKVMem::grouped(4, 1, &cfg).unwrap();
+    assert_eq!(
+        gqa.try_step(vec![0.0; 8], vec![0.0; 8], vec![0.0; 2], false).err(),
+        Some(KVMemError::DimensionMismatch { name: "k", expected: 2, got: 8 })
+    );
+    assert!(gqa.try_step(vec![0.0; 8], vec![0.0; 2], vec![0.0; 2], false).is_ok());
+}
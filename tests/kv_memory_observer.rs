@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+
+use kv_memory_vsc_v0::{KVMemV0, MemoryObserver};
+
+mod common;
+use common::{build, builder};
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Write(usize),
+    Reuse(usize),
+    Gate(usize),
+    Evict(usize, Vec<f64>, Vec<f64>),
+    WindowEvict(Vec<f64>),
+    Read(usize),
+}
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<Event>>);
+
+impl Recorder {
+    fn take(&self) -> Vec<Event> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl MemoryObserver for Recorder {
+    fn on_write(&self, slot: usize, _key: &[f64], _value: &[f64]) {
+        self.0.lock().unwrap().push(Event::Write(slot));
+    }
+
+    fn on_reuse(&self, slot: usize, _similarity: f64, _key: &[f64], _value: &[f64]) {
+        self.0.lock().unwrap().push(Event::Reuse(slot));
+    }
+
+    fn on_gate(&self, slot: usize, _similarity: f64) {
+        self.0.lock().unwrap().push(Event::Gate(slot));
+    }
+
+    fn on_evict(&self, slot: usize, key: &[f64], value: &[f64], _age: u64) {
+        self.0.lock().unwrap().push(Event::Evict(slot, key.to_vec(), value.to_vec()));
+    }
+
+    fn on_window_evict(&self, key: &[f64], _value: &[f64]) {
+        self.0.lock().unwrap().push(Event::WindowEvict(key.to_vec()));
+    }
+
+    fn on_read(&self, _q: &[f64], weights: &[f64], _output: &[f64]) {
+        self.0.lock().unwrap().push(Event::Read(weights.len()));
+    }
+}
+
+fn mem() -> KVMemV0 {
+    build(builder(1, 1, 2).tau_reuse(0.85).tau_novel(0.5))
+}
+
+#[test]
+fn lifecycle_events_in_order() {
+    let rec = Arc::new(Recorder::default());
+    let mut m = mem();
+    m.set_observer(rec.clone());
+
+    let _ = m.step(vec![0.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0], true);
+    assert_eq!(rec.take(), vec![Event::Write(0), Event::Read(2)]);
+
+    m.ingest(&[2.0, 0.0], &[0.0, 3.0], true);
+    assert_eq!(rec.take(), vec![Event::Reuse(0), Event::WindowEvict(vec![1.0, 0.0])]);
+
+    m.ingest(&[1.0, 1.0], &[0.0, 0.0], true);
+    assert_eq!(rec.take(), vec![Event::Gate(0), Event::WindowEvict(vec![2.0, 0.0])]);
+
+    m.ingest(&[0.0, 1.0], &[5.0, 0.0], true);
+    assert_eq!(
+        rec.take(),
+        vec![Event::Evict(0, vec![2.0, 0.0], vec![0.0, 3.0]), Event::Write(0), Event::WindowEvict(vec![1.0, 1.0])]
+    );
+
+    let _ = m.query_batch(&[vec![1.0, 0.0], vec![0.0, 1.0]]);
+    assert_eq!(rec.take(), vec![Event::Read(2), Event::Read(2)]);
+}
+
+#[test]
+fn observer_does_not_change_state() {
+    let mut a = mem();
+    let mut b = mem();
+    let rec = Arc::new(Recorder::default());
+    b.set_observer(rec.clone());
+    for t in 0..6 {
+        let k = if t % 2 == 0 { vec![1.0, 0.0] } else { vec![0.0, 1.0] };
+        assert_eq!(a.step(k.clone(), k.clone(), vec![t as f64, 1.0], true), b.step(k.clone(), k, vec![t as f64, 1.0], true));
+    }
+    assert_eq!(a.state_sha256(), b.state_sha256());
+
+    assert!(!rec.take().is_empty());
+
+    b.clear_observer();
+    let _ = b.step(vec![1.0, 0.0], vec![1.0, 0.0], vec![1.0, 0.0], false);
+    assert!(rec.take().is_empty());
+}
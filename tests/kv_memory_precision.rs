@@ -0,0 +1,105 @@
+use kv_memory_vsc_v0::{KVMemConfig, KVMemV0, Scalar};
+
+fn e<S: Scalar>(d: usize, i: usize, s: f64) -> Vec<S> {
+    let mut v = vec![S::default(); d];
+    v[i] = S::from_f64(s);
+    v
+}
+
+fn is_hit<S: Scalar>(out: &[S], idx: usize, thr: f64) -> bool {
+    let mut bi = 0usize;
+    let mut bv = -1.0f64;
+    for (i, v) in out.iter().enumerate() {
+        let a = v.to_f64().abs();
+        if a > bv {
+            bv = a;
+            bi = i;
+        }
+    }
+    bi == idx && bv > thr
+}
+
+fn mem<S: Scalar>(l: usize, m: usize, g_write: f64) -> KVMemV0<S> {
+    let cfg = KVMemConfig::builder().l_window(l).m_slots(m).d(8).tau_reuse(0.85).tau_novel(0.5).g_write(g_write).build().unwrap();
+    KVMemV0::with_config(&cfg).unwrap()
+}
+
+// tests/kv_memory_capacity.rs: three facts, 64 fill tokens, then ask each fact.
+fn capacity<S: Scalar>(m_slots: usize) -> Vec<bool> {
+    let d = 8;
+    let mut m = mem::<S>(8, m_slots, 1.0);
+    let z = vec![S::default(); d];
+    for i in 0..3 {
+        let _ = m.step(e(d, i, 10.0), e(d, i, 10.0), e(d, i, 100.0), true);
+        if m_slots == 2 && i < 2 {
+            let _ = m.step(z.clone(), z.clone(), z.clone(), false);
+        }
+    }
+    for _ in 0..64 {
+        let _ = m.step(z.clone(), z.clone(), z.clone(), false);
+    }
+    (0..3).map(|i| is_hit(&m.step(e(d, i, 10.0), z.clone(), z.clone(), false), i, 5.0)).collect()
+}
+
+// tests/kv_memory_fidelity_decay.rs: g=0.25 EMA decay of one fact, HIT/MISS per decay count.
+fn fidelity<S: Scalar>() -> Vec<bool> {
+    let d = 8;
+    let mut m = mem::<S>(1, 1, 0.25);
+    let z = vec![S::default(); d];
+    let _ = m.step(z.clone(), e(d, 0, 1.0), e(d, 0, 60.0), true);
+    (0..=6)
+        .map(|n| {
+            if n > 0 {
+                let _ = m.step(z.clone(), e(d, 0, 1.0), z.clone(), true);
+            }
+            let _ = m.step(z.clone(), e(d, 0, -10.0), z.clone(), false);
+            is_hit(&m.step(e(d, 0, 1.0), z.clone(), z.clone(), false), 0, 5.0)
+        })
+        .collect()
+}
+
+fn outcomes<S: Scalar>() -> Vec<Vec<bool>> {
+    vec![capacity::<S>(0), capacity::<S>(2), capacity::<S>(3), fidelity::<S>()]
+}
+
+#[test]
+fn f64_outcomes_match_v0_tests() {
+    assert_eq!(
+        outcomes::<f64>(),
+        vec![
+            vec![false, false, false],
+            vec![false, true, true],
+            vec![true, true, true],
+            vec![true, true, false, false, false, false, false],
+        ]
+    );
+}
+
+#[test]
+fn f32_matches_f64_hit_miss() {
+    assert_eq!(outcomes::<f32>(), outcomes::<f64>());
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn bf16_matches_f64_hit_miss() {
+    assert_eq!(outcomes::<half::bf16>(), outcomes::<f64>());
+}
+
+#[test]
+fn scalar_type_is_part_of_state_hash() {
+    let a = mem::<f64>(2, 1, 1.0);
+    let b = mem::<f32>(2, 1, 1.0);
+    assert_ne!(a.state_sha256(), b.state_sha256());
+    assert_ne!(a.memory_kv_sha256(), b.memory_kv_sha256());
+
+    // f64 keeps its v0 hashes, pinned from the baseline implementation. Memory-free so
+    // that no later per-slot state (occupancy) enters the hash.
+    let mut v0 = mem::<f64>(2, 0, 1.0);
+    for i in 0..3 {
+        let x = e::<f64>(8, i, 1.5 + i as f64);
+        let _ = v0.step(x.clone(), x.clone(), x, true);
+    }
+    assert_eq!(v0.state_sha256(), "1bd37fd4046516fc258144c2c82be358880f5f69c15d2e09427110f289159c92");
+    assert_eq!(v0.memory_kv_sha256(), "070743e1b10f2fdc9bb5e4f435077c81eef425d9cdd11844948f1eb0e7e1aad6");
+}
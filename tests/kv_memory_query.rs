@@ -0,0 +1,121 @@
+use kv_memory_vsc_v0::{KVMemV0, KeyOrigin, MultiHeadKVMem};
+
+mod common;
+use common::{build, builder, e, max_abs};
+
+const D: usize = 8;
+
+fn mem(l_window: usize, m_slots: usize) -> KVMemV0 {
+    build(builder(l_window, m_slots, D).tau_reuse(0.85).tau_novel(0.5))
+}
+
+#[test]
+fn step_is_ingest_then_query() {
+    let mut a = mem(2, 2);
+    let mut b = mem(2, 2);
+    for t in 0..10 {
+        let (q, k, v) = (e(D, (t + 3) % D, 10.0), e(D, t % D, 10.0), e(D, t % D, 100.0));
+        let out = a.step(q.clone(), k.clone(), v.clone(), t % 2 == 0);
+        b.ingest(&k, &v, t % 2 == 0);
+        assert_eq!(out, b.query(&q));
+        assert_eq!(a.memory_kv_sha256(), b.memory_kv_sha256());
+    }
+}
+
+#[test]
+fn query_leaves_state_unchanged() {
+    let mut m = mem(2, 2);
+    m.ingest(&e(D, 0, 10.0), &e(D, 0, 100.0), true);
+    for _ in 0..4 {
+        m.ingest(&[0.0; D], &[0.0; D], false);
+    }
+    let before = m.state_sha256();
+
+    // Fact 0 is out of the L=2 window; repeated probes see the same memory.
+    let first = m.query(&e(D, 0, 10.0));
+    assert!(max_abs(&first) > 5.0);
+    for _ in 0..3 {
+        assert_eq!(m.query(&e(D, 0, 10.0)), first);
+    }
+    assert_eq!(m.state_sha256(), before);
+    assert_eq!(m.window_len(), 2);
+    assert_eq!(m.read_hits(), &[0, 0]);
+}
+
+#[test]
+fn multihead_step_is_ingest_then_query() {
+    let head = builder(2, 1, 2).build().unwrap();
+    let mut a = MultiHeadKVMem::grouped(4, 2, &head).unwrap();
+    let mut b = a.clone();
+    let (q, k, v) = (vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, -1.0], vec![1.0, 0.0, 0.0, 1.0], vec![3.0, 4.0, 5.0, 6.0]);
+    let out = a.step(q.clone(), k.clone(), v.clone(), true);
+    b.ingest(&k, &v, true);
+    assert_eq!(out, b.query(&q));
+    assert_eq!(a.state_sha256(), b.state_sha256());
+}
+
+#[test]
+fn query_batch_matches_single_queries() {
+    let mut m = mem(2, 2);
+    for i in 0..3 {
+        m.ingest(&e(D, i, 10.0), &e(D, i, 100.0), true);
+    }
+    let qs: Vec<Vec<f64>> = (0..D).map(|i| e(D, i, 10.0)).collect();
+    let before = m.state_sha256();
+    let batch = m.query_batch(&qs);
+    assert_eq!(batch, qs.iter().map(|q| m.query(q)).collect::<Vec<_>>());
+    assert_eq!(m.state_sha256(), before);
+
+    #[cfg(feature = "rayon")]
+    assert_eq!(m.par_query_batch(&qs), batch);
+}
+
+#[test]
+fn answer_is_attributed_to_its_slot() {
+    let mut m = mem(8, 3);
+    for i in 0..3 {
+        m.ingest(&e(D, i, 10.0), &e(D, i, 100.0), true);
+    }
+    for _ in 0..64 {
+        m.ingest(&[0.0; D], &[0.0; D], false);
+    }
+
+    let t = m.query_explain(&e(D, 2, 10.0));
+    assert_eq!(t.output, m.query(&e(D, 2, 10.0)));
+    assert_eq!(t.argmax(), Some(KeyOrigin::Memory(2)));
+    assert_eq!(t.origins.len(), 8 + 3);
+    assert_eq!(t.origins[0], KeyOrigin::Window(0));
+    assert_eq!(t.origins[8], KeyOrigin::Memory(0));
+    assert_eq!(t.scale, (D as f64).sqrt());
+    assert_eq!(t.scores[10], 100.0);
+    assert!(t.weight_of(KeyOrigin::Memory(2)).unwrap() > 0.99);
+    assert!((t.window_mass() + t.memory_mass() - 1.0).abs() < 1e-12);
+    assert!(t.window_mass() < 0.01);
+}
+
+#[test]
+fn step_with_trace_matches_step() {
+    let mut a = mem(2, 2);
+    let mut b = mem(2, 2);
+    for t in 0..8 {
+        let (q, k, v) = (e(D, (t + 1) % 4, 3.0), e(D, t % 4, 3.0), e(D, t % 4, 50.0));
+        let out = a.step(q.clone(), k.clone(), v.clone(), true);
+        let trace = b.step_with_trace(&q, &k, &v, true);
+        assert_eq!(out, trace.read.output);
+        assert_eq!(a.state_sha256(), b.state_sha256());
+        assert_eq!(a.read_hits(), b.read_hits());
+    }
+}
+
+#[test]
+fn window_positions_run_oldest_to_newest() {
+    let mut m = mem(3, 0);
+    for i in 0..5 {
+        m.ingest(&e(D, i, 10.0), &e(D, i, 1.0), false);
+    }
+    // Window holds tokens 2, 3, 4; the query matches token 3.
+    let t = m.query_explain(&e(D, 3, 10.0));
+    assert_eq!(t.origins, vec![KeyOrigin::Window(0), KeyOrigin::Window(1), KeyOrigin::Window(2)]);
+    assert_eq!(t.argmax(), Some(KeyOrigin::Window(1)));
+    assert_eq!(t.memory_mass(), 0.0);
+}
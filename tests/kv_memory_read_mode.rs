@@ -0,0 +1,67 @@
+use kv_memory_vsc_v0::{KVMemConfig, KVMemError, KVMemV0, ReadMode};
+
+mod common;
+use common::{build, builder, e};
+
+const D: usize = 8;
+
+fn mem(l_window: usize, m_slots: usize, read_mode: ReadMode) -> KVMemV0 {
+    build(builder(l_window, m_slots, D).read_mode(read_mode))
+}
+
+// One fact in memory, then a full window of weakly matching distractors.
+fn window_pressure(read_mode: ReadMode) -> Vec<f64> {
+    let mut m = mem(256, 1, read_mode);
+    m.ingest(&e(D, 0, 3.0), &e(D, 0, 100.0), true);
+    for _ in 0..256 {
+        m.ingest(&e(D, 0, 2.0), &e(D, 1, 100.0), false);
+    }
+    m.query(&e(D, 0, 3.0))
+}
+
+#[test]
+fn gated_reads_resist_window_count() {
+    let joint = window_pressure(ReadMode::Joint);
+    assert!(joint[1] > joint[0]);
+
+    let fixed = window_pressure(ReadMode::FixedGate { memory_weight: 0.75 });
+    assert!((fixed[0] - 75.0).abs() < 1e-9 && (fixed[1] - 25.0).abs() < 1e-9);
+
+    // Gate = sigmoid((9 - 6) / sqrt(8)) ~ 0.74 regardless of how many distractors there are.
+    let scored = window_pressure(ReadMode::ScoreGate { temperature: 1.0 });
+    let g = 1.0 / (1.0 + (-3.0 / (D as f64).sqrt()).exp());
+    assert!((scored[0] - 100.0 * g).abs() < 1e-9);
+}
+
+#[test]
+fn gate_weights_sum_to_one_and_handle_empty_sides() {
+    let mut m = mem(4, 2, ReadMode::FixedGate { memory_weight: 0.25 });
+    let _ = m.step(vec![0.0; D], e(D, 0, 1.0), e(D, 0, 1.0), true);
+    let t = m.query_explain(&e(D, 1, 1.0));
+    assert!((t.memory_mass() - 0.25).abs() < 1e-15);
+    assert!((t.window_mass() - 0.75).abs() < 1e-15);
+
+    // No window: occupied memory gets everything. No memory: the window does.
+    let mut m = mem(0, 2, ReadMode::FixedGate { memory_weight: 0.25 });
+    m.ingest(&e(D, 0, 1.0), &e(D, 0, 1.0), true);
+    assert_eq!(m.query_explain(&e(D, 0, 1.0)).memory_mass(), 1.0);
+    let mut m = mem(2, 0, ReadMode::ScoreGate { temperature: 1.0 });
+    m.ingest(&e(D, 0, 1.0), &e(D, 0, 1.0), false);
+    assert_eq!(m.query_explain(&e(D, 0, 1.0)).window_mass(), 1.0);
+}
+
+#[test]
+fn read_mode_is_validated_hashed_and_snapshotted() {
+    let bad = KVMemConfig::builder().read_mode(ReadMode::FixedGate { memory_weight: 1.5 }).build();
+    assert_eq!(bad, Err(KVMemError::WeightOutOfRange { name: "memory_weight", value: 1.5 }));
+    let bad = KVMemConfig::builder().read_mode(ReadMode::ScoreGate { temperature: 0.0 }).build();
+    assert_eq!(bad, Err(KVMemError::NotPositive { name: "temperature", value: 0.0 }));
+
+    let joint = KVMemConfig::default();
+    let gated = KVMemConfig { read_mode: ReadMode::ScoreGate { temperature: 0.5 }, ..KVMemConfig::default() };
+    assert_ne!(joint.sha256(), gated.sha256());
+
+    let m = KVMemV0::from_config(&gated).unwrap();
+    let restored = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    assert_eq!(restored.config(), gated);
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use kv_memory_vsc_v0::{KVMemV0, Lfu, Replacement, ReplacementPolicy, SlotMeta};
+
+mod common;
+use common::{build, builder, e, max_abs};
+
+const D: usize = 8;
+
+fn mem(replacement: Replacement) -> KVMemV0 {
+    build(builder(1, 2, D).tau_reuse(0.85).tau_novel(0.5).g_write(1.0).replacement(replacement))
+}
+
+fn write(m: &mut KVMemV0, idx: usize, key_scale: f64) {
+    let _ = m.step(vec![0.0; D], e(D, idx, key_scale), e(D, idx, 100.0), true);
+}
+
+fn recalls(m: &mut KVMemV0, idx: usize) -> bool {
+    // Push a neutral token first so the fact is not in the L=1 window.
+    let _ = m.step(vec![0.0; D], vec![0.0; D], vec![0.0; D], false);
+    let out = m.step(e(D, idx, 10.0), vec![0.0; D], vec![0.0; D], false);
+    max_abs(&out) > 5.0 && out[idx].abs() == max_abs(&out)
+}
+
+#[test]
+fn lru_config_is_v0_behaviour() {
+    let mut a = mem(Replacement::Lru);
+    let mut b = KVMemV0::new(1, 2, D, 0.85, 0.5, 1.0);
+    for idx in [0, 1, 0, 2, 3] {
+        write(&mut a, idx, 10.0);
+        write(&mut b, idx, 10.0);
+    }
+    assert_eq!(a.state_sha256(), b.state_sha256());
+    assert_ne!(mem(Replacement::Fifo).state_sha256(), KVMemV0::new(1, 2, D, 0.85, 0.5, 1.0).state_sha256());
+}
+
+#[test]
+fn fifo_ignores_reuse_refresh() {
+    let run = |r: Replacement| {
+        let mut m = mem(r);
+        write(&mut m, 0, 10.0);
+        write(&mut m, 1, 10.0);
+        write(&mut m, 0, 10.0); // reuse: refreshes A's age, not its insertion
+        write(&mut m, 2, 10.0);
+        (recalls(&mut m, 0), recalls(&mut m, 1))
+    };
+    assert_eq!(run(Replacement::Lru), (true, false));
+    assert_eq!(run(Replacement::Fifo), (false, true));
+}
+
+#[test]
+fn lfu_and_least_attended_keep_the_recalled_fact() {
+    let run = |r: Replacement| {
+        let mut m = mem(r);
+        write(&mut m, 0, 10.0);
+        write(&mut m, 1, 10.0);
+        for _ in 0..3 {
+            assert!(recalls(&mut m, 0));
+        }
+        write(&mut m, 2, 10.0);
+        (recalls(&mut m, 0), recalls(&mut m, 1))
+    };
+    assert_eq!(run(Replacement::Lru), (false, true));
+    assert_eq!(run(Replacement::Lfu), (true, false));
+    assert_eq!(run(Replacement::LeastAttended), (true, false));
+}
+
+#[test]
+fn lowest_norm_evicts_the_weak_key() {
+    let run = |r: Replacement| {
+        let mut m = mem(r);
+        write(&mut m, 0, 10.0);
+        write(&mut m, 1, 1.0);
+        write(&mut m, 2, 10.0);
+        (recalls(&mut m, 0), recalls(&mut m, 2))
+    };
+    assert_eq!(run(Replacement::Lru), (false, true));
+    assert_eq!(run(Replacement::LowestNorm), (true, true));
+}
+
+// Evicts the most recently written slot.
+struct Mru;
+
+impl ReplacementPolicy for Mru {
+    fn victim(&self, s: &SlotMeta<'_>) -> usize {
+        (0..s.len()).min_by_key(|&i| s.age(i)).unwrap()
+    }
+}
+
+#[test]
+fn custom_policy_replaces_the_configured_one() {
+    let keys = [1.0, 0.0, 0.0, 1.0];
+    let meta = SlotMeta::new(2, &keys, &[3, 1], &[3, 1], &[5, 2], &[0.0, 0.0]);
+    assert_eq!((Mru.victim(&meta), Lfu.victim(&meta)), (1, 1));
+    assert_eq!(meta.key(1).collect::<Vec<_>>(), vec![0.0, 1.0]);
+
+    let run = |custom: bool| {
+        let mut m = mem(Replacement::Lru);
+        if custom {
+            m.set_replacement_policy(Arc::new(Mru));
+        }
+        write(&mut m, 0, 10.0);
+        write(&mut m, 1, 10.0);
+        write(&mut m, 2, 10.0);
+        ((recalls(&mut m, 0), recalls(&mut m, 1)), m)
+    };
+    assert_eq!(run(false).0, (false, true));
+    let (recalled, mut m) = run(true);
+    assert_eq!(recalled, (true, false));
+
+    // Not part of the config or snapshot: a restore falls back to LRU.
+    assert_eq!(m.config(), mem(Replacement::Lru).config());
+    let mut restored = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    write(&mut m, 3, 10.0);
+    write(&mut restored, 3, 10.0);
+    assert_ne!(m.to_state().km, restored.to_state().km);
+}
@@ -0,0 +1,104 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use kv_memory_vsc_v0::{KVMemConfig, KVMemV0, MultiHeadKVMem, WriteRule};
+
+mod common;
+use common::{Rng, builder};
+
+// Counts allocations per thread, so concurrently running tests do not interfere.
+struct Counting;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static COUNTING: Counting = Counting;
+
+fn cfg() -> KVMemConfig {
+    builder(5, 3, 4).tau_reuse(0.8).tau_novel(0.3).g_write(0.5).build().unwrap()
+}
+
+#[test]
+fn step_into_matches_step_across_window_wraparound() {
+    let mut a = KVMemV0::from_config(&cfg()).unwrap();
+    let mut b = a.clone();
+    let mut rng = Rng(0x9e3779b97f4a7c15);
+    let mut out = vec![0.0; 4];
+    for t in 0..40 {
+        let (q, k, v) = (rng.vec(4), rng.vec(4), rng.vec(4));
+        let want = a.step(q.clone(), k.clone(), v.clone(), t % 3 == 0);
+        b.step_into(&q, &k, &v, t % 3 == 0, &mut out);
+        assert_eq!(want, out, "t={}", t);
+        assert_eq!(a.state_sha256(), b.state_sha256());
+    }
+    assert_eq!(a.window_len(), 5);
+}
+
+#[test]
+fn step_into_does_not_allocate() {
+    let rules = [WriteRule::Ema, WriteRule::Overwrite, WriteRule::Delta, WriteRule::RunningMean, WriteRule::MaxNorm];
+    for rule in rules {
+        let cfg = builder(5, 3, 4).tau_reuse(0.8).tau_novel(0.3).write_rule(rule).memory_top_k(Some(2)).slot_ttl(Some(6));
+        let mut m = KVMemV0::from_config(&cfg.build().unwrap()).unwrap();
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        let inputs: Vec<_> = (0..40).map(|_| (rng.vec(4), rng.vec(4), rng.vec(4))).collect();
+        let mut out = vec![0.0; 4];
+        let before = ALLOCS.with(Cell::get);
+        for (t, (q, k, v)) in inputs.iter().enumerate() {
+            m.step_into(q, k, v, t % 2 == 0, &mut out);
+        }
+        assert_eq!(ALLOCS.with(Cell::get), before, "{:?}", rule);
+        assert!(m.stats().novel_writes > 0 && m.stats().reuse_updates + m.stats().evictions > 0, "{:?}", rule);
+    }
+}
+
+#[test]
+fn wrapped_window_hashes_in_logical_order() {
+    let mut m = KVMemV0::from_config(&cfg()).unwrap();
+    let mut rng = Rng(0x9e3779b97f4a7c15);
+    for t in 0..23 {
+        let _ = m.step(rng.vec(4), rng.vec(4), rng.vec(4), t % 3 == 0);
+    }
+    // Pinned from the Vec::remove(0) implementation; the ring head is not at row 0 here.
+    // Re-pinned when slot occupancy entered both hashes (same states, extra bytes).
+    assert_eq!(m.state_sha256(), "40074ed465a87af5db32205721930f6a9e964699329117a17c40f827359282f8");
+    assert_eq!(m.memory_kv_sha256(), "3cfeea303910bd3157bacbde5093c187321b3e28c5055450d336589072f99cd7");
+
+    // A snapshot restores the window unrotated; the logical state and hash are unchanged.
+    let mut restored = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    assert_eq!(restored.state_sha256(), m.state_sha256());
+    for _ in 0..7 {
+        let (q, k, v) = (rng.vec(4), rng.vec(4), rng.vec(4));
+        assert_eq!(m.step(q.clone(), k.clone(), v.clone(), true), restored.step(q, k, v, true));
+    }
+    assert_eq!(restored.state_sha256(), m.state_sha256());
+}
+
+#[test]
+fn multihead_step_into_matches_step() {
+    let head = builder(3, 2, 2).build().unwrap();
+    let mut a = MultiHeadKVMem::grouped(4, 2, &head).unwrap();
+    let mut b = a.clone();
+    let mut rng = Rng(7);
+    let mut out = vec![0.0; a.d_model()];
+    for t in 0..12 {
+        let (q, k, v) = (rng.vec(8), rng.vec(4), rng.vec(4));
+        let want = a.step(q.clone(), k.clone(), v.clone(), t % 2 == 0);
+        b.step_into(&q, &k, &v, t % 2 == 0, &mut out);
+        assert_eq!(want, out);
+    }
+    assert_eq!(a.state_sha256(), b.state_sha256());
+}
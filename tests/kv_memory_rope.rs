@@ -0,0 +1,126 @@
+use kv_memory_vsc_v0::{KVMemConfig, KVMemError, KVMemV0, KeyOrigin, MemoryPosition, Rope, SnapshotError};
+use sha2::{Digest, Sha256};
+
+mod common;
+use common::{build, builder, e};
+
+const D: usize = 8;
+
+fn mem(l_window: usize, m_slots: usize, rope: Option<Rope>) -> KVMemV0 {
+    build(builder(l_window, m_slots, D).rope(rope))
+}
+
+#[test]
+fn window_scores_depend_on_relative_position_only() {
+    let rope = Some(Rope::new(10.0));
+    let tokens: Vec<_> = (0..4).map(|i| (e(D, 0, 2.0), e(D, i, 1.0))).collect();
+
+    // Same window contents after different numbers of earlier tokens.
+    let mut a = mem(4, 0, rope);
+    let mut b = mem(4, 0, rope);
+    for _ in 0..7 {
+        b.ingest(&e(D, 1, 5.0), &e(D, 1, 5.0), false);
+    }
+    for (k, v) in &tokens {
+        a.ingest(k, v, false);
+        b.ingest(k, v, false);
+    }
+    assert_eq!(a.query(&e(D, 0, 2.0)), b.query(&e(D, 0, 2.0)));
+    assert_eq!(a.state_sha256(), b.state_sha256());
+
+    // Identical keys are told apart by recency; without RoPE they tie.
+    let t = a.query_explain(&e(D, 0, 2.0));
+    assert_eq!(t.argmax(), Some(KeyOrigin::Window(3)));
+    assert!(t.weights[0] != t.weights[3]);
+    let mut plain = mem(4, 0, None);
+    for (k, v) in &tokens {
+        plain.ingest(k, v, false);
+    }
+    let t = plain.query_explain(&e(D, 0, 2.0));
+    assert_eq!(t.weights[0], t.weights[3]);
+}
+
+fn slot_score(m: &KVMemV0, q: &[f64]) -> f64 {
+    let t = m.query_explain(q);
+    t.scores[t.origins.iter().position(|&o| o == KeyOrigin::Memory(0)).unwrap()]
+}
+
+#[test]
+fn memory_position_policy() {
+    let mut free = mem(2, 1, Some(Rope::new(10.0)));
+    let mut pinned = mem(2, 1, Some(Rope { base: 10.0, memory: MemoryPosition::WritePosition }));
+    let q = e(D, 0, 3.0);
+    for m in [&mut free, &mut pinned] {
+        m.ingest(&e(D, 0, 3.0), &e(D, 0, 1.0), true);
+    }
+    // Distance 0 right after the write: both score the plain dot product.
+    assert!((slot_score(&pinned, &q) - 9.0).abs() < 1e-12);
+    assert_eq!(slot_score(&free, &q), 9.0);
+
+    for m in [&mut free, &mut pinned] {
+        for _ in 0..3 {
+            m.ingest(&e(D, 1, 1.0), &e(D, 1, 1.0), false);
+        }
+    }
+    assert_eq!(pinned.slot_write_positions(), &[0]);
+    assert_eq!(pinned.position(), 4);
+    assert_eq!(slot_score(&free, &q), 9.0);
+    let expected = 9.0 * 3.0f64.cos();
+    assert!((slot_score(&pinned, &q) - expected).abs() < 1e-12);
+}
+
+#[test]
+fn rope_is_hashed_and_persisted() {
+    let rope = Rope { base: 100.0, memory: MemoryPosition::WritePosition };
+    let mut m = mem(2, 2, Some(rope));
+    let mut plain = mem(2, 2, None);
+    for i in 0..5 {
+        m.ingest(&e(D, i % 3, 3.0), &e(D, i, 1.0), true);
+        plain.ingest(&e(D, i % 3, 3.0), &e(D, i, 1.0), true);
+    }
+    assert_ne!(m.config().sha256(), plain.config().sha256());
+    assert_ne!(m.state_sha256(), plain.state_sha256());
+
+    let restored = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    assert_eq!(restored.state_sha256(), m.state_sha256());
+    assert_eq!(restored.slot_write_positions(), m.slot_write_positions());
+    let via_state = KVMemV0::from_state(m.to_state()).unwrap();
+    assert_eq!(via_state.query(&e(D, 1, 3.0)), m.query(&e(D, 1, 3.0)));
+
+    let bad = KVMemConfig::builder().rope(Some(Rope::new(0.0))).build();
+    assert!(matches!(bad, Err(KVMemError::NotPositive { name: "rope_base", .. })));
+}
+
+#[test]
+fn write_positions_ahead_of_position_are_rejected() {
+    let rope = Rope { base: 100.0, memory: MemoryPosition::WritePosition };
+    let mut m = mem(2, 2, Some(rope));
+    m.ingest(&e(D, 0, 3.0), &e(D, 0, 1.0), true);
+    m.ingest(&e(D, 1, 1.0), &e(D, 1, 1.0), false);
+
+    let mut s = m.to_state();
+    s.write_pos[0] = 10;
+    assert_eq!(KVMemV0::from_state(s).err(), Some(KVMemError::WritePosition { slot: 0, write_pos: 10, position: 2 }));
+    // Slot 1 was never written.
+    let mut s = m.to_state();
+    s.write_pos[1] = 1;
+    assert_eq!(KVMemV0::from_state(s).err(), Some(KVMemError::WritePosition { slot: 1, write_pos: 1, position: 2 }));
+
+    // write_pos[0] sits before write_count and occupancy at the end of the body.
+    let mut bytes = m.to_snapshot_bytes();
+    bytes.truncate(bytes.len() - 32);
+    let off = bytes.len() - 2 - 2 * 8 - 2 * 8;
+    assert_eq!(bytes[off..off + 8], 0u64.to_le_bytes());
+    bytes[off..off + 8].copy_from_slice(&10u64.to_le_bytes());
+    let sum = Sha256::digest(&bytes);
+    bytes.extend_from_slice(&sum);
+    assert!(matches!(KVMemV0::from_snapshot_bytes(&bytes), Err(SnapshotError::Corrupt(_))));
+
+    // Expiry frees the slot and clears its write position, so the state stays loadable.
+    let mut m = build(builder(2, 2, D).rope(Some(rope)).slot_ttl(Some(1)));
+    m.ingest(&e(D, 0, 3.0), &e(D, 0, 1.0), true);
+    m.ingest(&e(D, 1, 3.0), &e(D, 1, 1.0), true);
+    m.ingest(&e(D, 2, 1.0), &e(D, 2, 1.0), false);
+    assert_eq!((m.slot_occupancy(), m.slot_write_positions()), (&[false, true][..], &[0, 1][..]));
+    assert!(KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).is_ok());
+}
@@ -0,0 +1,150 @@
+use kv_memory_vsc_v0::{KVMemConfig, KVMemError, KVMemV0, KeyOrigin, ReadMode, ReadRefresh, Replacement, WriteOutcome};
+
+mod common;
+use common::{build, builder, e};
+
+const D: usize = 4;
+
+fn mem(m_slots: usize, tau_novel: f64) -> KVMemV0 {
+    build(builder(1, m_slots, D).tau_reuse(0.9).tau_novel(tau_novel))
+}
+
+#[test]
+fn zero_key_write_occupies_a_slot() {
+    let fresh = mem(2, 0.5);
+    let mut m = fresh.clone();
+    let z = vec![0.0; D];
+    assert_eq!(m.ingest(&z, &z, true), WriteOutcome::Written { slot: 0, similarity: f64::NEG_INFINITY, evicted_age: None });
+    assert_eq!(m.slot_occupancy(), &[true, false]);
+    // Same all-zero slot rows as before the write; only occupancy tells them apart.
+    assert_eq!(m.to_state().km, fresh.to_state().km);
+    assert_ne!(m.memory_kv_sha256(), fresh.memory_kv_sha256());
+
+    // Cosine 0 against the zero key is below tau_novel; the novel write prefers the empty slot.
+    assert!(matches!(m.ingest(&e(D, 0, 1.0), &e(D, 0, 1.0), true), WriteOutcome::Written { slot: 1, .. }));
+    assert_eq!(m.slot_occupancy(), &[true, true]);
+}
+
+#[test]
+fn empty_slots_take_no_read_weight() {
+    let mut m = mem(4, 0.5);
+    m.ingest(&e(D, 0, 1.0), &e(D, 0, 8.0), true);
+    m.ingest(&e(D, 1, 1.0), &e(D, 1, 1.0), false);
+
+    // Query orthogonal to both keys: uniform over the window token and slot 0 only.
+    let t = m.query_explain(&e(D, 2, 1.0));
+    assert_eq!(t.weight_of(KeyOrigin::Memory(0)), Some(0.5));
+    for j in 1..4 {
+        assert_eq!(t.weight_of(KeyOrigin::Memory(j)), Some(0.0));
+    }
+
+    // Nothing occupied: reads see the window alone.
+    let mut m = mem(4, 0.5);
+    m.ingest(&e(D, 1, 1.0), &e(D, 1, 3.0), false);
+    assert_eq!(m.query(&e(D, 2, 1.0)), e(D, 1, 3.0));
+    assert_eq!(m.nearest_slot(&e(D, 0, 1.0)), None);
+}
+
+#[test]
+fn empty_slots_score_no_hits_or_refreshes() {
+    // No window and no occupied slot: every logit is -inf, and a zero hit threshold
+    // would count any weight the softmax fallback handed out.
+    for read_mode in [ReadMode::Joint, ReadMode::FixedGate { memory_weight: 0.5 }] {
+        let cfg = builder(0, 2, D)
+            .read_mode(read_mode)
+            .read_hit_threshold(0.0)
+            .read_refresh(ReadRefresh::Reset)
+            .build()
+            .unwrap();
+        let mut m = KVMemV0::from_config(&cfg).unwrap();
+        let out = m.step(e(D, 0, 1.0), e(D, 0, 1.0), e(D, 0, 1.0), false);
+        assert_eq!(out, vec![0.0; D], "{:?}", read_mode);
+        assert_eq!(m.read_hits(), &[0, 0]);
+        assert_eq!(m.slot_ages(), &[1, 1]);
+        assert_eq!(m.stats().mean_memory_mass(), Some(0.0));
+
+        // Once slot 0 is written it takes all the weight; slot 1 stays untouched.
+        let _ = m.step(e(D, 0, 1.0), e(D, 0, 1.0), e(D, 0, 1.0), true);
+        assert_eq!(m.read_hits(), &[1, 0]);
+        assert_eq!(m.slot_ages(), &[0, 2]);
+    }
+}
+
+#[test]
+fn non_positive_tau_novel_still_fills_empty_memory() {
+    // An empty slot has no similarity to clear tau_novel; the first write is always novel.
+    let mut m = mem(2, -1.0);
+    assert!(matches!(m.ingest(&e(D, 0, 1.0), &e(D, 0, 5.0), true), WriteOutcome::Written { slot: 0, .. }));
+    // cos = 0 >= tau_novel: gated against slot 0 rather than taking the empty slot 1.
+    assert!(matches!(m.ingest(&e(D, 1, 1.0), &e(D, 1, 5.0), true), WriteOutcome::Gated { slot: 0, .. }));
+    assert_eq!(m.slot_occupancy(), &[true, false]);
+}
+
+#[test]
+fn occupancy_round_trips_and_is_checked() {
+    let mut m = mem(3, 0.5);
+    let z = vec![0.0; D];
+    m.ingest(&z, &z, true);
+    m.ingest(&e(D, 1, 1.0), &e(D, 1, 2.0), true);
+
+    let restored = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    assert_eq!(restored.slot_occupancy(), &[true, true, false]);
+    assert_eq!(restored.state_sha256(), m.state_sha256());
+
+    let mut s = m.to_state();
+    assert_eq!(KVMemV0::from_state(s.clone()).unwrap().slot_occupancy(), m.slot_occupancy());
+    s.occupied.pop();
+    assert_eq!(
+        KVMemV0::from_state(s).err(),
+        Some(KVMemError::DimensionMismatch { name: "occupied", expected: 3, got: 2 })
+    );
+}
+
+#[test]
+fn values_and_keys_decay_per_step() {
+    let cfg = builder(1, 1, D).key_decay(0.5).value_decay(0.75).build().unwrap();
+    let mut m = KVMemV0::from_config(&cfg).unwrap();
+    m.ingest(&e(D, 0, 2.0), &e(D, 1, 8.0), true);
+    for _ in 0..3 {
+        m.ingest(&e(D, 2, 1.0), &e(D, 2, 1.0), false);
+    }
+    let s = m.to_state();
+    assert_eq!(s.km[0], e(D, 0, 2.0 * 0.125));
+    assert_eq!(s.vm[0], e(D, 1, 8.0 * 0.421875));
+}
+
+#[test]
+fn expired_slots_are_zeroed_and_reused_first() {
+    // FIFO would evict A (inserted first); B expires, so C takes B's free slot instead.
+    let cfg = builder(1, 2, D).replacement(Replacement::Fifo).slot_ttl(Some(3)).build().unwrap();
+    let mut m = KVMemV0::from_config(&cfg).unwrap();
+    m.ingest(&e(D, 0, 1.0), &e(D, 0, 5.0), true);
+    m.ingest(&e(D, 1, 1.0), &e(D, 1, 5.0), true);
+    for t in 0..4 {
+        // Reuse writes keep A's age at 0.
+        m.ingest(&e(D, 0, 1.0), &e(D, 0, 5.0), true);
+        assert_eq!(m.to_state().vm[1] == e(D, 1, 5.0), t < 3, "step {}", t);
+    }
+    assert_eq!(m.to_state().km[1], vec![0.0; D]);
+    assert_eq!(m.stats().expirations, 1);
+
+    m.ingest(&e(D, 2, 1.0), &e(D, 2, 5.0), true);
+    let s = m.to_state();
+    assert_eq!((s.vm[0].clone(), s.vm[1].clone()), (e(D, 0, 5.0), e(D, 2, 5.0)));
+}
+
+#[test]
+fn decay_settings_are_validated_hashed_and_persisted() {
+    let cfg = KVMemConfig::builder().value_decay(0.9).slot_ttl(Some(5)).build().unwrap();
+    assert_ne!(cfg.sha256(), KVMemConfig::default().sha256());
+    assert_eq!(KVMemConfig { value_decay: 1.0, slot_ttl: None, ..cfg.clone() }, KVMemConfig::default());
+
+    let mut m = KVMemV0::from_config(&cfg).unwrap();
+    m.ingest(&[1.0, 0.0], &[0.0, 3.0], true);
+    let restored = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    assert_eq!(restored.config(), cfg);
+    assert_eq!(restored.state_sha256(), m.state_sha256());
+
+    assert_eq!(KVMemConfig::builder().slot_ttl(Some(0)).build(), Err(KVMemError::ZeroTtl));
+    assert_eq!(KVMemConfig::builder().key_decay(0.0).build(), Err(KVMemError::GainOutOfRange { name: "key_decay", value: 0.0 }));
+}
@@ -0,0 +1,78 @@
+use kv_memory_vsc_v0::{KVMemV0, SnapshotError, SNAPSHOT_MAGIC};
+use sha2::{Digest, Sha256};
+
+mod common;
+use common::e;
+
+fn populated() -> KVMemV0 {
+    let d = 8usize;
+    let mut m = KVMemV0::new(4, 2, d, 0.85, 0.5, 0.25);
+    for i in 0..3 {
+        let _ = m.step(e(d, i, 1.0), e(d, i, 10.0), e(d, i, 100.0), true);
+        let _ = m.step(vec![0.0; d], e(d, 7, 1.0), e(d, 6, 3.0), false);
+    }
+    m
+}
+
+#[test]
+fn save_load_roundtrip_is_bit_identical() {
+    let d = 8usize;
+    let mut a = populated();
+
+    let mut buf = Vec::new();
+    a.save(&mut buf).unwrap();
+    assert_eq!(&buf[..8], &SNAPSHOT_MAGIC);
+
+    let mut b = KVMemV0::load(&mut buf.as_slice()).unwrap();
+    assert_eq!(a.state_sha256(), b.state_sha256());
+    assert_eq!(a.memory_kv_sha256(), b.memory_kv_sha256());
+    assert_eq!(a.config(), b.config());
+
+    for i in 0..8 {
+        let oa = a.step(e(d, i % 3, 10.0), e(d, i, 2.0), e(d, i, 5.0), i % 2 == 0);
+        let ob = b.step(e(d, i % 3, 10.0), e(d, i, 2.0), e(d, i, 5.0), i % 2 == 0);
+        assert_eq!(oa, ob);
+    }
+    assert_eq!(a.state_sha256(), b.state_sha256());
+    assert_eq!(a.to_snapshot_bytes(), b.to_snapshot_bytes());
+}
+
+#[test]
+fn load_rejects_corruption() {
+    let bytes = populated().to_snapshot_bytes();
+
+    let mut flipped = bytes.clone();
+    let mid = flipped.len() / 2;
+    flipped[mid] ^= 0x01;
+    assert!(matches!(KVMemV0::from_snapshot_bytes(&flipped), Err(SnapshotError::ChecksumMismatch)));
+
+    assert!(matches!(KVMemV0::from_snapshot_bytes(&bytes[..bytes.len() - 1]), Err(SnapshotError::ChecksumMismatch)));
+    assert!(matches!(KVMemV0::from_snapshot_bytes(&bytes[..12]), Err(SnapshotError::Truncated)));
+
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert!(matches!(KVMemV0::from_snapshot_bytes(&extra), Err(SnapshotError::ChecksumMismatch)));
+
+    let mut magic = bytes.clone();
+    magic[0] = b'X';
+    assert!(matches!(KVMemV0::from_snapshot_bytes(&magic), Err(SnapshotError::BadMagic)));
+
+    let mut version = bytes;
+    version[8] = 99;
+    assert!(matches!(KVMemV0::from_snapshot_bytes(&version), Err(SnapshotError::UnsupportedVersion(99))));
+}
+
+#[test]
+fn load_rejects_sizes_the_body_does_not_hold() {
+    let bytes = populated().to_snapshot_bytes();
+    // m_slots follows magic, version, spec id and l_window.
+    let off = 8 + 4 + 4 + "kv-memory/v0".len() + 8;
+    assert_eq!(bytes[off..off + 8], 2u64.to_le_bytes());
+    for m_slots in [3u64, 1 << 40, u64::MAX] {
+        let mut patched = bytes[..bytes.len() - 32].to_vec();
+        patched[off..off + 8].copy_from_slice(&m_slots.to_le_bytes());
+        let sum = Sha256::digest(&patched);
+        patched.extend_from_slice(&sum);
+        assert!(matches!(KVMemV0::from_snapshot_bytes(&patched), Err(SnapshotError::Corrupt(_))), "m_slots = {}", m_slots);
+    }
+}
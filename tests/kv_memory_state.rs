@@ -0,0 +1,82 @@
+use kv_memory_vsc_v0::{KVMemError, KVMemV0};
+
+mod common;
+use common::e;
+
+fn populated() -> KVMemV0 {
+    let d = 4usize;
+    let mut m = KVMemV0::new(3, 2, d, 0.85, 0.5, 0.25);
+    let _ = m.step(e(d, 0, 1.0), e(d, 0, 10.0), e(d, 0, 0.1 + 0.2), true);
+    let _ = m.step(e(d, 1, 1.0), e(d, 1, 10.0), e(d, 1, -7.5), true);
+    let _ = m.step(vec![0.0; d], e(d, 3, 1.0), e(d, 2, 1e-300), false);
+    m
+}
+
+#[test]
+fn state_roundtrip_preserves_hashes() {
+    let m = populated();
+    let state = m.to_state();
+    assert_eq!(state.spec_id, "kv-memory/v0");
+    assert_eq!(state.kw.len(), 3);
+
+    let r = KVMemV0::from_state(state.clone()).unwrap();
+    assert_eq!(r.state_sha256(), m.state_sha256());
+    assert_eq!(r.to_state(), state);
+}
+
+#[test]
+fn from_state_rejects_inconsistent_shapes() {
+    let mut state = populated().to_state();
+    state.vm.pop();
+    assert_eq!(
+        KVMemV0::from_state(state).err(),
+        Some(KVMemError::DimensionMismatch { name: "vm", expected: 2, got: 1 })
+    );
+
+    // A config claiming more slots than the state holds fails before allocating them.
+    let mut state = populated().to_state();
+    state.config.m_slots = 1 << 40;
+    assert_eq!(
+        KVMemV0::from_state(state).err(),
+        Some(KVMemError::DimensionMismatch { name: "km", expected: 1 << 40, got: 2 })
+    );
+
+    // A short window is valid, so a huge l_window can only fail at allocation.
+    let mut state = populated().to_state();
+    state.config.l_window = 1 << 57;
+    assert_eq!(KVMemV0::from_state(state).err(), Some(KVMemError::SizeOverflow { name: "l_window * d" }));
+
+    let mut state = populated().to_state();
+    state.spec_id = "kv-memory/v1".to_string();
+    assert!(matches!(KVMemV0::from_state(state), Err(KVMemError::SpecMismatch(_))));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn canonical_json_is_sorted_compact_and_exact() {
+    let m = populated();
+    let json = m.to_canonical_json();
+
+    assert!(!json.contains(' '));
+    let keys = ["\"age\"", "\"config\"", "\"km\"", "\"kw\"", "\"spec_id\"", "\"vm\"", "\"vw\""];
+    let pos: Vec<usize> = keys.iter().map(|k| json.find(k).unwrap()).collect();
+    assert!(pos.windows(2).all(|w| w[0] < w[1]), "{}", json);
+    assert!(json.contains("\"0.30000000000000004\""));
+    assert!(json.contains("\"1e-300\""));
+
+    let r = KVMemV0::from_json(&json).unwrap();
+    assert_eq!(r.state_sha256(), m.state_sha256());
+    assert_eq!(r.to_canonical_json(), json);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_config_accepts_plain_numbers() {
+    let mut state = populated().to_state();
+    state.kw.clear();
+    state.vw.clear();
+    let json = state.to_canonical_json().replace("\"tau_reuse\":\"0.85\"", "\"tau_reuse\":0.85");
+    assert!(json.contains("\"tau_reuse\":0.85"));
+    let r = KVMemV0::from_json(&json).unwrap();
+    assert_eq!(r.config(), populated().config());
+}
@@ -0,0 +1,51 @@
+use kv_memory_vsc_v0::KVMemV0;
+
+mod common;
+use common::{build, builder};
+
+fn mem() -> KVMemV0 {
+    build(builder(1, 1, 2).tau_reuse(0.85).tau_novel(0.5))
+}
+
+#[test]
+fn counts_each_write_branch() {
+    let mut m = mem();
+    assert_eq!(m.stats().mean_similarity(), None);
+
+    m.ingest(&[1.0, 0.0], &[0.0, 1.0], true); // novel into empty memory: no slot to compare, not sampled
+    m.ingest(&[2.0, 0.0], &[0.0, 3.0], true); // reuse: sim 1
+    m.ingest(&[1.0, 1.0], &[0.0, 0.0], true); // gated: sim ~0.707
+    m.ingest(&[0.0, 1.0], &[5.0, 0.0], true); // novel, evicts slot 0: sim 0
+    m.ingest(&[0.0, 0.0], &[0.0, 0.0], false);
+
+    let s = m.stats();
+    assert_eq!((s.steps, s.write_events), (5, 4));
+    assert_eq!((s.reuse_updates, s.novel_writes, s.gated, s.evictions), (1, 2, 1, 1));
+    assert_eq!(s.similarity_samples, 3);
+    assert_eq!(s.max_similarity(), Some(1.0));
+    let mean = (1.0 + 0.5f64.sqrt()) / 3.0;
+    assert!((s.mean_similarity().unwrap() - mean).abs() < 1e-12);
+    assert_eq!(s.reads, 0);
+
+    m.reset_stats();
+    assert_eq!(m.stats().steps, 0);
+}
+
+#[test]
+fn step_reads_split_mass_between_memory_and_window() {
+    let mut m = mem();
+    let _ = m.step(vec![0.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0], true);
+    // Query orthogonal to both keys: uniform over window token and slot.
+    let _ = m.step(vec![0.0, 1.0], vec![1.0, 0.0], vec![0.0, 1.0], false);
+    let _ = m.query(&[1.0, 0.0]);
+
+    let s = m.stats();
+    assert_eq!(s.reads, 2);
+    assert_eq!(s.mean_memory_mass(), Some(0.5));
+    assert_eq!(s.mean_window_mass(), Some(0.5));
+
+    // Stats are diagnostics, not state.
+    let fresh = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    assert_eq!(fresh.state_sha256(), m.state_sha256());
+    assert_eq!(fresh.stats().reads, 0);
+}
@@ -0,0 +1,67 @@
+use kv_memory_vsc_v0::{AttentionTrace, KVMemConfig, KVMemError, KVMemV0, KeyOrigin};
+
+mod common;
+use common::{build, builder, e};
+
+const D: usize = 8;
+
+fn mem(memory_top_k: Option<usize>) -> KVMemV0 {
+    build(builder(2, 4, D).tau_reuse(0.85).tau_novel(0.5).memory_top_k(memory_top_k))
+}
+
+#[test]
+fn top_k_equal_to_m_slots_matches_full_attention() {
+    let mut full = mem(None);
+    let mut top = mem(Some(4));
+    for t in 0..24 {
+        let (q, k, v) = (e(D, (t * 3) % D, 2.0), e(D, t % D, 1.0 + t as f64), e(D, (t + 1) % D, 10.0));
+        assert_eq!(full.step(q.clone(), k.clone(), v.clone(), true), top.step(q, k, v, true));
+    }
+    assert_eq!(full.memory_kv_sha256(), top.memory_kv_sha256());
+    assert_eq!(full.read_hits(), top.read_hits());
+}
+
+#[test]
+fn only_top_k_slots_receive_weight() {
+    let mut m = mem(Some(2));
+    let mut full = mem(None);
+    for i in 0..4 {
+        m.ingest(&e(D, i, 1.0), &e(D, i, 100.0), true);
+        full.ingest(&e(D, i, 1.0), &e(D, i, 100.0), true);
+    }
+    // Query scores slots 3 > 1 > 2 > 0.
+    let q = vec![1.0, 3.0, 2.0, 4.0, 0.0, 0.0, 0.0, 0.0];
+    let t = m.query_explain(&q);
+    assert_eq!(t.weight_of(KeyOrigin::Memory(0)), Some(0.0));
+    assert_eq!(t.weight_of(KeyOrigin::Memory(2)), Some(0.0));
+    assert!(t.weight_of(KeyOrigin::Memory(1)).unwrap() > 0.0);
+    assert!(t.weight_of(KeyOrigin::Memory(3)).unwrap() > 0.0);
+    assert!((t.window_mass() + t.memory_mass() - 1.0).abs() < 1e-12);
+
+    // Scores are unchanged; only the dropped slots' weight is redistributed.
+    let f = full.query_explain(&q);
+    assert_eq!(t.scores, f.scores);
+    let ratio = |t: &AttentionTrace| t.weights[0] / t.weights[1];
+    assert!((ratio(&t) - ratio(&f)).abs() < 1e-12);
+}
+
+#[test]
+fn ties_go_to_lowest_index() {
+    let mut m = mem(Some(1));
+    for i in 0..4 {
+        m.ingest(&e(D, i, 1.0), &e(D, i, 100.0), true);
+    }
+    let t = m.query_explain(&[1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+    let memory: Vec<f64> = (0..4).map(|j| t.weight_of(KeyOrigin::Memory(j)).unwrap()).collect();
+    assert!(memory[0] > 0.0);
+    assert_eq!(&memory[1..], &[0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn zero_top_k_is_rejected() {
+    assert_eq!(KVMemConfig::builder().memory_top_k(Some(0)).build(), Err(KVMemError::ZeroTopK));
+    let cfg = KVMemConfig { memory_top_k: Some(3), ..KVMemConfig::default() };
+    assert_ne!(cfg.sha256(), KVMemConfig::default().sha256());
+    let m = KVMemV0::from_config(&cfg).unwrap();
+    assert_eq!(KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap().config(), cfg);
+}
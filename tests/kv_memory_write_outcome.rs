@@ -0,0 +1,53 @@
+use kv_memory_vsc_v0::{KVMemV0, WriteOutcome};
+
+mod common;
+use common::{build, builder};
+
+fn mem(m_slots: usize) -> KVMemV0 {
+    build(builder(8, m_slots, 2).tau_reuse(0.85).tau_novel(0.50))
+}
+
+#[test]
+fn novelty_gate_blocks_duplicate_write() {
+    let mut m = mem(1);
+    let fact = m.step_with_trace(&[0.0, 0.0], &[1.0, 0.0], &[0.0, 1.0], true);
+    // Nothing occupied yet: no slot to compare against.
+    assert_eq!(fact.write, WriteOutcome::Written { slot: 0, similarity: f64::NEG_INFINITY, evicted_age: None });
+
+    // cos([1, 0], [1, 1]) ~ 0.707: in [tau_novel, tau_reuse), so the write is dropped.
+    let h = m.memory_kv_sha256();
+    let dup = m.step_with_trace(&[0.0, 0.0], &[1.0, 1.0], &[1.0, 0.0], true);
+    match dup.write {
+        WriteOutcome::Gated { slot: 0, similarity } => assert!((similarity - 0.5f64.sqrt()).abs() < 1e-12),
+        other => panic!("expected Gated, got {:?}", other),
+    }
+    assert!(!dup.write.wrote_memory());
+    assert_eq!(m.memory_kv_sha256(), h);
+}
+
+#[test]
+fn reuse_and_novel_writes_report_slot_and_evicted_age() {
+    let mut m = mem(2);
+    assert_eq!(
+        m.ingest(&[1.0, 0.0], &[0.0, 1.0], true),
+        WriteOutcome::Written { slot: 0, similarity: f64::NEG_INFINITY, evicted_age: None }
+    );
+    assert_eq!(m.ingest(&[2.0, 0.0], &[0.0, 3.0], true), WriteOutcome::Reused { slot: 0, similarity: 1.0 });
+    assert_eq!(m.ingest(&[0.0, 1.0], &[1.0, 0.0], false), WriteOutcome::NoWrite);
+
+    // Orthogonal to slot 0; the empty slot 1 is filled first, evicting nothing.
+    assert_eq!(m.ingest(&[0.0, 1.0], &[1.0, 0.0], true), WriteOutcome::Written { slot: 1, similarity: 0.0, evicted_age: None });
+
+    // Anti-aligned key: best similarity is 0 (slot 1), and LRU slot 0 is evicted at age 3.
+    assert_eq!(
+        m.ingest(&[-1.0, 0.0], &[5.0, 5.0], true),
+        WriteOutcome::Written { slot: 0, similarity: 0.0, evicted_age: Some(3) }
+    );
+}
+
+#[test]
+fn no_memory_reports_no_memory() {
+    let mut m = mem(0);
+    assert_eq!(m.ingest(&[1.0, 0.0], &[0.0, 1.0], true), WriteOutcome::NoMemory);
+    assert_eq!(WriteOutcome::NoMemory.slot(), None);
+}
@@ -0,0 +1,97 @@
+use kv_memory_vsc_v0::{KVMemConfig, KVMemV0, WriteOutcome, WriteRule};
+
+mod common;
+use common::{build, builder};
+
+fn mem(m_slots: usize, g_write: f64, write_rule: WriteRule) -> KVMemV0 {
+    build(builder(1, m_slots, 2).g_write(g_write).write_rule(write_rule))
+}
+
+fn value(m: &KVMemV0, slot: usize) -> Vec<f64> {
+    m.to_state().vm[slot].clone()
+}
+
+#[test]
+fn reuse_writes_per_rule() {
+    let run = |rule| {
+        let mut m = mem(1, 0.5, rule);
+        for v in [[3.0, 4.0], [0.0, 0.0], [6.0, 0.0]] {
+            m.ingest(&[1.0, 0.0], &v, true);
+        }
+        value(&m, 0)
+    };
+    // EMA from a zero slot: 1.5,2 -> 0.75,1 -> 3.375,0.5.
+    assert_eq!(run(WriteRule::Ema), vec![3.375, 0.5]);
+    assert_eq!(run(WriteRule::Delta), run(WriteRule::Ema));
+    assert_eq!(run(WriteRule::Overwrite), vec![6.0, 0.0]);
+    let mean = run(WriteRule::RunningMean);
+    assert!((mean[0] - 3.0).abs() < 1e-12 && (mean[1] - 4.0 / 3.0).abs() < 1e-12);
+
+    // Norms never shrink: the first write lands at |[3, 4]|, and the zero write keeps it.
+    let mut m = mem(1, 0.5, WriteRule::MaxNorm);
+    m.ingest(&[1.0, 0.0], &[3.0, 4.0], true);
+    assert_eq!(value(&m, 0), vec![3.0, 4.0]);
+    m.ingest(&[1.0, 0.0], &[0.0, 0.0], true);
+    assert_eq!(value(&m, 0), vec![3.0, 4.0]);
+}
+
+#[test]
+fn delta_rule_corrects_toward_memory_prediction() {
+    let mut m = mem(2, 0.5, WriteRule::Delta);
+    m.ingest(&[1.0, 0.0], &[10.0, 0.0], true);
+    m.ingest(&[0.0, 1.0], &[0.0, 10.0], true);
+    let before = m.to_state();
+    assert_eq!((before.km[0].clone(), before.km[1].clone()), (vec![0.5, 0.0], vec![0.0, 0.5]));
+    m.ingest(&[1.0, 0.0], &[20.0, 0.0], true);
+
+    // read([1, 0]): softmax of logits [0.5, 0] / sqrt(2) over the two slot values.
+    let w0 = 1.0 / (1.0 + (-0.5 / 2f64.sqrt()).exp());
+    let got = value(&m, 0);
+    for (x, want) in [20.0, 0.0].into_iter().enumerate() {
+        let read = w0 * before.vm[0][x] + (1.0 - w0) * before.vm[1][x];
+        assert!((got[x] - (before.vm[0][x] + 0.5 * (want - read))).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn delta_rule_predicts_from_occupied_slots_only() {
+    let mut m = mem(3, 0.5, WriteRule::Delta);
+    // Empty memory predicts zero: 0 + 0.5 * (10 - 0).
+    m.ingest(&[1.0, 0.0], &[10.0, 0.0], true);
+    assert_eq!(value(&m, 0), vec![5.0, 0.0]);
+    // Slot 0 is the only occupied slot, so read([1, 0]) is its value, undiluted by
+    // the empty slots: 5 + 0.5 * (20 - 5).
+    m.ingest(&[1.0, 0.0], &[20.0, 0.0], true);
+    assert_eq!(value(&m, 0), vec![12.5, 0.0]);
+}
+
+#[test]
+fn delta_rule_eviction_predicts_from_the_victim() {
+    let run = |rule| {
+        let mut m = mem(1, 0.5, rule);
+        m.ingest(&[1.0, 0.0], &[10.0, 0.0], true);
+        // cos = -1 < tau_novel: a novel write evicting slot 0.
+        let outcome = m.ingest(&[-1.0, 0.0], &[0.0, 8.0], true);
+        assert!(matches!(outcome, WriteOutcome::Written { slot: 0, evicted_age: Some(_), .. }));
+        value(&m, 0)
+    };
+    // read([-1, 0]) is the victim's old value [5, 0]: 5 + 0.5 * (0 - 5), 0 + 0.5 * (8 - 0).
+    assert_eq!(run(WriteRule::Delta), vec![2.5, 4.0]);
+    assert_eq!(run(WriteRule::Delta), run(WriteRule::Ema));
+}
+
+#[test]
+fn running_mean_counts_restart_on_novel_write_and_persist() {
+    let mut m = mem(1, 0.5, WriteRule::RunningMean);
+    m.ingest(&[1.0, 0.0], &[2.0, 0.0], true);
+    m.ingest(&[1.0, 0.0], &[4.0, 0.0], true);
+    assert_eq!(m.slot_write_counts(), &[2]);
+    m.ingest(&[-1.0, 0.0], &[0.0, 8.0], true);
+    assert_eq!(m.slot_write_counts(), &[1]);
+    assert_eq!(value(&m, 0), vec![0.0, 8.0]);
+
+    let restored = KVMemV0::from_snapshot_bytes(&m.to_snapshot_bytes()).unwrap();
+    assert_eq!(restored.state_sha256(), m.state_sha256());
+    assert_eq!(KVMemV0::from_state(m.to_state()).unwrap().slot_write_counts(), &[1]);
+    assert_ne!(m.config().sha256(), KVMemConfig { write_rule: WriteRule::Ema, ..m.config() }.sha256());
+}